@@ -0,0 +1,397 @@
+// src/bin/copymaster-helper.rs
+// Ayudante con privilegios de CopyMaster. Se lanza con pkexec cuando una copia
+// falla por permisos y hace solo las operaciones que fallaron (crear, escribir,
+// chmod/chown, renombrar) sobre las rutas exactas que recibe como argumentos.
+//
+// Reglas:
+// - Cada ruta debe ser absoluta, sin `.` ni `..`, y coincidir con una autorizada.
+// - Los directorios intermedios se recorren con O_NOFOLLOW: un enlace
+//   simbólico en el camino hace fallar la operación.
+// - Solo se escribe, cambia de permisos o de propietario lo creado en esta sesión,
+//   y solo se renombra un archivo creado en esta sesión.
+// - Nunca se ponen bits setuid, ni setgid en archivos.
+// - Solo se cede la propiedad al usuario que lanzó pkexec y a sus grupos.
+#[path = "../core/helper_protocol.rs"]
+mod protocol;
+#[cfg(test)]
+#[path = "../core/test_support.rs"]
+mod test_support;
+
+use std::collections::{HashMap, HashSet};
+use std::ffi::{CString, OsStr};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::os::unix::fs::FileExt;
+use std::os::unix::io::{AsRawFd, FromRawFd, OwnedFd};
+use std::path::{Component, Path, PathBuf};
+
+use nix::fcntl::{openat, renameat, OFlag};
+use nix::sys::stat::{fchmod, mkdirat, Mode};
+use nix::unistd::{fchown, getgrouplist, getuid, unlinkat, Gid, Uid, UnlinkatFlags, User};
+
+use protocol::{HelperRequest, HelperResponse, MAX_WRITE_LEN};
+
+/// Permisos admitidos: los normales, más setgid en directorios compartidos
+const FILE_MODE_MASK: u32 = 0o777;
+const DIR_MODE_MASK: u32 = 0o2777;
+
+/// Lo que se ha creado en esta sesión, abierto para no volver a resolver la ruta
+struct Created {
+    file: File,
+    is_dir: bool,
+}
+
+struct Session {
+    granted: HashSet<PathBuf>,
+    created: HashMap<PathBuf, Created>,
+    uid: Uid,
+    groups: Vec<Gid>,
+}
+
+fn os_failure(error: impl Into<std::io::Error>) -> HelperResponse {
+    let error = error.into();
+    HelperResponse::Failed {
+        os_error: error.raw_os_error(),
+        message: error.to_string(),
+    }
+}
+
+/// Ruta absoluta hecha solo de nombres normales
+fn check_path(path: &Path) -> Result<(), String> {
+    let mut components = path.components();
+    if components.next() != Some(Component::RootDir) {
+        return Err(format!("{} no es una ruta absoluta", path.display()));
+    }
+    if components.clone().next().is_none() {
+        return Err("la raíz no se puede modificar".to_string());
+    }
+    if !components.all(|component| matches!(component, Component::Normal(_))) {
+        return Err(format!("{} contiene componentes no permitidos", path.display()));
+    }
+    Ok(())
+}
+
+/// Abrir el directorio que contiene `path` sin seguir enlaces simbólicos
+fn open_parent(path: &Path) -> Result<(OwnedFd, &OsStr), HelperResponse> {
+    let name = path.file_name().ok_or_else(|| HelperResponse::Refused(format!("{} no tiene nombre", path.display())))?;
+    let flags = OFlag::O_DIRECTORY | OFlag::O_RDONLY | OFlag::O_CLOEXEC;
+
+    let root = nix::fcntl::open("/", flags, Mode::empty()).map_err(os_failure)?;
+    let mut dir = unsafe { OwnedFd::from_raw_fd(root) };
+
+    for component in path.parent().into_iter().flat_map(|parent| parent.components()) {
+        if let Component::Normal(part) = component {
+            let next = openat(dir.as_raw_fd(), part, flags | OFlag::O_NOFOLLOW, Mode::empty()).map_err(os_failure)?;
+            dir = unsafe { OwnedFd::from_raw_fd(next) };
+        }
+    }
+
+    Ok((dir, name))
+}
+
+/// Usuario que lanzó pkexec; sin pkexec, el propio
+fn invoking_user() -> Uid {
+    std::env::var("PKEXEC_UID")
+        .ok()
+        .and_then(|uid| uid.parse().ok())
+        .map(Uid::from_raw)
+        .unwrap_or_else(getuid)
+}
+
+fn user_groups(uid: Uid) -> Result<Vec<Gid>, String> {
+    let user = User::from_uid(uid)
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("el usuario {} no existe", uid))?;
+    let name = CString::new(user.name.as_bytes()).map_err(|e| e.to_string())?;
+    getgrouplist(&name, user.gid).map_err(|e| e.to_string())
+}
+
+impl Session {
+    fn new(granted: HashSet<PathBuf>) -> Result<Self, String> {
+        let uid = invoking_user();
+        Ok(Self {
+            granted,
+            created: HashMap::new(),
+            uid,
+            groups: user_groups(uid)?,
+        })
+    }
+
+    fn check_granted(&self, path: &Path) -> Result<(), HelperResponse> {
+        check_path(path).map_err(HelperResponse::Refused)?;
+        if !self.granted.contains(path) {
+            return Err(HelperResponse::Refused(format!("{} no está autorizada", path.display())));
+        }
+        Ok(())
+    }
+
+    fn check_mode(mode: u32, is_dir: bool) -> Result<Mode, HelperResponse> {
+        let mask = if is_dir { DIR_MODE_MASK } else { FILE_MODE_MASK };
+        if mode & !mask != 0 {
+            return Err(HelperResponse::Refused(format!("permisos {:o} no permitidos", mode)));
+        }
+        Ok(Mode::from_bits_truncate(mode as nix::sys::stat::mode_t))
+    }
+
+    fn created(&self, path: &Path) -> Result<&Created, HelperResponse> {
+        self.created
+            .get(path)
+            .ok_or_else(|| HelperResponse::Refused(format!("{} no se creó en esta sesión", path.display())))
+    }
+
+    fn handle(&mut self, request: HelperRequest, input: &mut impl Read) -> Result<(), HelperResponse> {
+        match request {
+            HelperRequest::CreateDir { path, mode } => {
+                self.check_granted(&path)?;
+                let mode = Self::check_mode(mode, true)?;
+                let (parent, name) = open_parent(&path)?;
+
+                mkdirat(parent.as_raw_fd(), name, mode).map_err(os_failure)?;
+                let fd = openat(
+                    parent.as_raw_fd(),
+                    name,
+                    OFlag::O_DIRECTORY | OFlag::O_RDONLY | OFlag::O_NOFOLLOW | OFlag::O_CLOEXEC,
+                    Mode::empty(),
+                )
+                .map_err(os_failure)?;
+                let file = unsafe { File::from_raw_fd(fd) };
+                // mkdirat aplica la umask
+                fchmod(file.as_raw_fd(), mode).map_err(os_failure)?;
+
+                self.created.insert(path, Created { file, is_dir: true });
+                Ok(())
+            }
+            HelperRequest::CreateFile { path, mode } => {
+                self.check_granted(&path)?;
+                let mode = Self::check_mode(mode, false)?;
+                let (parent, name) = open_parent(&path)?;
+
+                let fd = openat(
+                    parent.as_raw_fd(),
+                    name,
+                    OFlag::O_CREAT | OFlag::O_EXCL | OFlag::O_WRONLY | OFlag::O_NOFOLLOW | OFlag::O_CLOEXEC,
+                    mode,
+                )
+                .map_err(os_failure)?;
+                let file = unsafe { File::from_raw_fd(fd) };
+                fchmod(file.as_raw_fd(), mode).map_err(os_failure)?;
+
+                self.created.insert(path, Created { file, is_dir: false });
+                Ok(())
+            }
+            HelperRequest::Write { path, offset, len } => {
+                // Los datos se leen siempre para no perder el hilo del protocolo
+                let mut data = vec![0u8; len as usize];
+                input.read_exact(&mut data).map_err(os_failure)?;
+
+                let created = self.created(&path)?;
+                if created.is_dir {
+                    return Err(HelperResponse::Refused(format!("{} es un directorio", path.display())));
+                }
+                created.file.write_all_at(&data, offset).map_err(os_failure)
+            }
+            HelperRequest::SetPermissions { path, mode } => {
+                let created = self.created(&path)?;
+                let mode = Self::check_mode(mode, created.is_dir)?;
+                fchmod(created.file.as_raw_fd(), mode).map_err(os_failure)
+            }
+            HelperRequest::SetOwner { path, uid, gid } => {
+                let created = self.created(&path)?;
+                let (uid, gid) = (Uid::from_raw(uid), Gid::from_raw(gid));
+                if uid != self.uid || !self.groups.contains(&gid) {
+                    return Err(HelperResponse::Refused(format!(
+                        "solo se puede ceder {} al usuario {} y sus grupos",
+                        path.display(),
+                        self.uid
+                    )));
+                }
+                fchown(created.file.as_raw_fd(), Some(uid), Some(gid)).map_err(os_failure)
+            }
+            HelperRequest::Rename { from, to } => {
+                self.check_granted(&to)?;
+                let created = self.created(&from)?;
+                if created.is_dir {
+                    return Err(HelperResponse::Refused(format!("{} es un directorio", from.display())));
+                }
+                created.file.sync_all().map_err(os_failure)?;
+
+                let (from_parent, from_name) = open_parent(&from)?;
+                let (to_parent, to_name) = open_parent(&to)?;
+                renameat(Some(from_parent.as_raw_fd()), from_name, Some(to_parent.as_raw_fd()), to_name)
+                    .map_err(os_failure)?;
+                // Que el rename sobreviva a un corte de luz
+                File::from(to_parent).sync_all().map_err(os_failure)?;
+
+                self.created.remove(&from);
+                Ok(())
+            }
+            HelperRequest::Finish => Ok(()),
+        }
+    }
+
+    /// Borrar los archivos creados que no llegaron a renombrarse, para no dejar
+    /// temporales de root donde el usuario no puede borrarlos
+    fn discard_unfinished(&mut self) {
+        for (path, created) in self.created.drain() {
+            if created.is_dir {
+                continue;
+            }
+            if let Ok((parent, name)) = open_parent(&path) {
+                let _ = unlinkat(Some(parent.as_raw_fd()), name, UnlinkatFlags::NoRemoveDir);
+            }
+        }
+    }
+}
+
+fn reply(output: &mut impl Write, response: &HelperResponse) {
+    let sent = serde_json::to_writer(&mut *output, response)
+        .map_err(std::io::Error::from)
+        .and_then(|_| output.write_all(b"\n"))
+        .and_then(|_| output.flush());
+    // Sin nadie al otro lado no hay nada más que hacer
+    if sent.is_err() {
+        std::process::exit(1);
+    }
+}
+
+fn main() {
+    let mut output = std::io::stdout().lock();
+
+    let granted: Result<HashSet<PathBuf>, String> = std::env::args_os()
+        .skip(1)
+        .map(|arg| {
+            let path = PathBuf::from(arg);
+            check_path(&path).map(|_| path)
+        })
+        .collect();
+
+    let mut session = match granted.and_then(Session::new) {
+        Ok(session) => session,
+        Err(message) => {
+            reply(&mut output, &HelperResponse::Refused(message));
+            std::process::exit(2);
+        }
+    };
+
+    // Listo para recibir peticiones
+    reply(&mut output, &HelperResponse::Ok);
+
+    let mut input = BufReader::new(std::io::stdin().lock());
+    let mut line = String::new();
+    let mut status = 0;
+
+    loop {
+        line.clear();
+        match input.read_line(&mut line) {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {}
+        }
+
+        // Una petición ilegible o demasiado grande desincroniza el flujo: se corta
+        let request = match serde_json::from_str::<HelperRequest>(&line) {
+            Ok(HelperRequest::Write { len, .. }) if len > MAX_WRITE_LEN => {
+                reply(&mut output, &HelperResponse::Refused(format!("escritura de {} bytes demasiado grande", len)));
+                status = 2;
+                break;
+            }
+            Ok(request) => request,
+            Err(e) => {
+                reply(&mut output, &HelperResponse::Refused(format!("petición no válida: {}", e)));
+                status = 2;
+                break;
+            }
+        };
+
+        let finish = matches!(request, HelperRequest::Finish);
+        let response = match session.handle(request, &mut input) {
+            Ok(()) => HelperResponse::Ok,
+            Err(response) => response,
+        };
+        reply(&mut output, &response);
+
+        if finish {
+            break;
+        }
+    }
+
+    session.discard_unfinished();
+    std::process::exit(status);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::test_support::ScratchDir;
+    use std::io::Cursor;
+
+    fn session(granted: &[&Path]) -> Session {
+        Session {
+            granted: granted.iter().map(|path| path.to_path_buf()).collect(),
+            created: HashMap::new(),
+            uid: getuid(),
+            groups: vec![nix::unistd::getgid()],
+        }
+    }
+
+    fn refused(result: Result<(), HelperResponse>) -> bool {
+        matches!(result, Err(HelperResponse::Refused(_)))
+    }
+
+    #[test]
+    fn only_plain_absolute_paths_are_accepted() {
+        assert!(check_path(Path::new("/srv/datos/a")).is_ok());
+        assert!(check_path(Path::new("srv/datos/a")).is_err());
+        assert!(check_path(Path::new("/")).is_err());
+        assert!(check_path(Path::new("/srv/../etc/passwd")).is_err());
+    }
+
+    #[test]
+    fn special_mode_bits_are_refused() {
+        assert!(Session::check_mode(0o644, false).is_ok());
+        assert!(Session::check_mode(0o2775, true).is_ok());
+        assert!(Session::check_mode(0o2775, false).is_err());
+        assert!(Session::check_mode(0o4755, false).is_err());
+        assert!(Session::check_mode(0o1777, true).is_err());
+    }
+
+    #[test]
+    fn requests_outside_the_grant_are_refused() {
+        let dir = ScratchDir::new("helper-grant");
+        let (part, dest) = (dir.join(".a.copymaster-part"), dir.join("a"));
+        let mut session = session(&[&part]);
+        let mut input = Cursor::new(b"datos".to_vec());
+
+        // Solo lo creado en la sesión se puede escribir, y solo hacia rutas autorizadas
+        let write = HelperRequest::Write { path: part.clone(), offset: 0, len: 5 };
+        assert!(refused(session.handle(write, &mut input)));
+        let create = HelperRequest::CreateFile { path: dest.clone(), mode: 0o600 };
+        assert!(refused(session.handle(create, &mut input)));
+
+        session.handle(HelperRequest::CreateFile { path: part.clone(), mode: 0o600 }, &mut input).unwrap();
+        let rename = HelperRequest::Rename { from: part.clone(), to: dest.clone() };
+        assert!(refused(session.handle(rename, &mut input)));
+        let chown = HelperRequest::SetOwner { path: part.clone(), uid: getuid().as_raw() + 1, gid: 0 };
+        assert!(refused(session.handle(chown, &mut input)));
+
+        session.discard_unfinished();
+        assert!(!part.exists());
+    }
+
+    #[test]
+    fn granted_copy_is_written_and_renamed() {
+        let dir = ScratchDir::new("helper-copy");
+        let (part, dest) = (dir.join(".a.copymaster-part"), dir.join("a"));
+        let mut session = session(&[&part, &dest]);
+        let mut input = Cursor::new(b"hola mundo".to_vec());
+
+        session.handle(HelperRequest::CreateFile { path: part.clone(), mode: 0o600 }, &mut input).unwrap();
+        session.handle(HelperRequest::Write { path: part.clone(), offset: 0, len: 4 }, &mut input).unwrap();
+        session.handle(HelperRequest::Write { path: part.clone(), offset: 4, len: 6 }, &mut input).unwrap();
+        session.handle(HelperRequest::SetPermissions { path: part.clone(), mode: 0o644 }, &mut input).unwrap();
+        session.handle(HelperRequest::Rename { from: part.clone(), to: dest.clone() }, &mut input).unwrap();
+        session.discard_unfinished();
+
+        assert_eq!(std::fs::read(&dest).unwrap(), b"hola mundo");
+        assert!(!part.exists());
+    }
+}
@@ -46,6 +46,21 @@ pub enum Commands {
     
     /// Mostrar información del sistema
     SystemInfo,
+    
+    /// Cambiar la prioridad de una transferencia en cola
+    Prioritize {
+        /// Identificador de la transferencia
+        job_id: u64,
+        
+        /// background, normal, interactive o critical
+        priority: String,
+    },
+    
+    /// Medir la velocidad del dispositivo que contiene una carpeta
+    Benchmark {
+        /// Carpeta con permiso de escritura en el dispositivo
+        path: std::path::PathBuf,
+    },
 }
 
 pub fn parse_args() -> Cli {
@@ -97,6 +112,41 @@ pub async fn handle_cli_commands(cli: &Cli) -> Result<(), Box<dyn std::error::Er
                 
                 std::process::exit(0);
             }
+            Commands::Prioritize { job_id, priority } => {
+                let priority: crate::core::device_queue::QueuePriority = priority.parse()?;
+                let dir = crate::core::scheduler::requests_dir()
+                    .ok_or("No se encontró el directorio de configuración")?;
+                
+                crate::core::scheduler::request_priority_change(&dir, *job_id, priority.clone())?;
+                println!("✓ Prioridad de la transferencia {} cambiada a {:?}", job_id, priority);
+                std::process::exit(0);
+            }
+            Commands::Benchmark { path } => {
+                let mut monitor = crate::core::device_detector::DeviceMonitor::new();
+                let info = monitor.detect_device(path).await;
+                let device_id = info.device_id.ok_or("La carpeta no está en un dispositivo de bloque")?;
+                
+                println!("Midiendo {} ({:?})...", device_id, info.device_type);
+                let dir = path.clone();
+                let speed = tokio::task::spawn_blocking(move || crate::core::benchmark::run_quick_benchmark(&dir)).await??;
+                
+                let show = |value: Option<f64>| value.map(|v| format!("{:.1} MB/s", v)).unwrap_or_else(|| "-".to_string());
+                println!("  Lectura secuencial:   {}", show(speed.sequential_read_mbps));
+                println!("  Escritura secuencial: {}", show(speed.sequential_write_mbps));
+                println!("  Lectura aleatoria:    {}", show(speed.random_read_mbps));
+                println!("  Escritura aleatoria:  {}", show(speed.random_write_mbps));
+                
+                let mut config_manager = crate::core::config::ConfigManager::new()?;
+                config_manager.update_device_speed(&device_id, |cached| {
+                    cached.sequential_read_mbps = speed.sequential_read_mbps;
+                    cached.sequential_write_mbps = speed.sequential_write_mbps;
+                    cached.random_read_mbps = speed.random_read_mbps;
+                    cached.random_write_mbps = speed.random_write_mbps;
+                    cached.updated_at = speed.updated_at;
+                })?;
+                println!("✓ Velocidad guardada");
+                std::process::exit(0);
+            }
         }
     }
     
@@ -0,0 +1,173 @@
+// src/core/atomic_write.rs
+// Escritura en un temporal oculto junto al destino y rename al terminar
+use std::collections::HashSet;
+use std::ffi::OsString;
+use std::fs::File;
+use std::os::unix::ffi::{OsStrExt, OsStringExt};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+use xxhash_rust::xxh3::xxh3_64;
+
+use crate::core::error_recovery::{CopyError, IoOperation, IoResultExt};
+
+/// Sufijo de los archivos a medio copiar
+pub const PART_SUFFIX: &str = ".copymaster-part";
+
+/// Longitud máxima de un nombre de archivo en casi todos los sistemas de archivos
+const NAME_MAX: usize = 255;
+
+/// Temporales que este proceso está escribiendo o piensa reanudar
+fn active_parts() -> &'static Mutex<HashSet<PathBuf>> {
+    static ACTIVE: OnceLock<Mutex<HashSet<PathBuf>>> = OnceLock::new();
+    ACTIVE.get_or_init(Default::default)
+}
+
+/// Marcar `part` como en uso para que la limpieza de temporales no lo toque
+pub fn mark_active(part: &Path) {
+    active_parts().lock().unwrap_or_else(|p| p.into_inner()).insert(part.to_path_buf());
+}
+
+/// `part` ya no está en uso: se renombró, se borró o se abandona
+pub fn mark_inactive(part: &Path) {
+    active_parts().lock().unwrap_or_else(|p| p.into_inner()).remove(part);
+}
+
+pub fn is_active(part: &Path) -> bool {
+    active_parts().lock().unwrap_or_else(|p| p.into_inner()).contains(part)
+}
+
+/// Temporal donde se escribe `dest`: `.nombre.copymaster-part` en el mismo directorio,
+/// para que el rename final no cruce sistemas de archivos
+pub fn part_path(dest: &Path) -> PathBuf {
+    let name = dest.file_name().map(|name| name.as_bytes()).unwrap_or(b"");
+
+    let mut part = Vec::with_capacity(name.len() + PART_SUFFIX.len() + 1);
+    part.push(b'.');
+    if 1 + name.len() + PART_SUFFIX.len() <= NAME_MAX {
+        part.extend_from_slice(name);
+    } else {
+        // Nombres muy largos: se sustituyen por su hash para no pasar de NAME_MAX
+        part.extend_from_slice(format!("{:016x}", xxh3_64(name)).as_bytes());
+    }
+    part.extend_from_slice(PART_SUFFIX.as_bytes());
+
+    dest.with_file_name(OsString::from_vec(part))
+}
+
+pub fn is_part_file(path: &Path) -> bool {
+    path.file_name()
+        .map(|name| {
+            let name = name.as_bytes();
+            name.starts_with(b".") && name.ends_with(PART_SUFFIX.as_bytes())
+        })
+        .unwrap_or(false)
+}
+
+/// Volcar el temporal a disco y renombrarlo sobre `dest`.
+/// Después se sincroniza el directorio para que el rename sobreviva a un corte de luz.
+pub fn commit(part: &Path, dest: &Path) -> Result<(), CopyError> {
+    File::open(part)
+        .and_then(|file| file.sync_all())
+        .io_context(IoOperation::Sync, part)?;
+
+    std::fs::rename(part, dest).io_context(IoOperation::Rename, dest)?;
+
+    if let Some(parent) = dest.parent() {
+        let parent = if parent.as_os_str().is_empty() { Path::new(".") } else { parent };
+        if let Err(e) = File::open(parent).and_then(|dir| dir.sync_all()) {
+            log::warn!("No se pudo sincronizar {}: {}", parent.display(), e);
+        }
+    }
+
+    Ok(())
+}
+
+/// Borrar los temporales abandonados bajo `root`, sin seguir enlaces.
+/// Los de `keep` pertenecen a transferencias que aún se pueden reanudar; los
+/// marcados con `mark_active`, a copias en curso de este proceso.
+/// Devuelve cuántos bytes se han liberado.
+pub fn remove_stale_parts(root: &Path, keep: &HashSet<PathBuf>) -> u64 {
+    let mut freed = 0;
+    let mut pending = vec![root.to_path_buf()];
+
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+
+            if file_type.is_dir() {
+                pending.push(path);
+            } else if file_type.is_file() && is_part_file(&path) && !keep.contains(&path) && !is_active(&path) {
+                let size = entry.metadata().map(|m| m.len()).unwrap_or(0);
+                match std::fs::remove_file(&path) {
+                    Ok(()) => {
+                        log::info!("Temporal abandonado eliminado: {}", path.display());
+                        freed += size;
+                    }
+                    Err(e) => log::warn!("No se pudo borrar {}: {}", path.display(), e),
+                }
+            }
+        }
+    }
+
+    freed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::test_support::ScratchDir;
+
+    #[test]
+    fn part_path_is_a_hidden_sibling() {
+        let part = part_path(Path::new("/destino/fotos/a.jpg"));
+
+        assert_eq!(part, Path::new("/destino/fotos/.a.jpg.copymaster-part"));
+        assert!(is_part_file(&part));
+        assert!(!is_part_file(Path::new("/destino/fotos/a.jpg")));
+    }
+
+    #[test]
+    fn long_names_are_hashed_below_name_max() {
+        let long_a = "a".repeat(NAME_MAX);
+        let long_b = format!("{}b", "a".repeat(NAME_MAX - 1));
+        let part_a = part_path(&Path::new("/destino").join(&long_a));
+        let part_b = part_path(&Path::new("/destino").join(&long_b));
+
+        let name_a = part_a.file_name().unwrap().len();
+        assert!(name_a <= NAME_MAX);
+        assert!(is_part_file(&part_a));
+        // El mismo nombre da siempre el mismo temporal, y nombres distintos, temporales distintos
+        assert_eq!(part_a, part_path(&Path::new("/destino").join(&long_a)));
+        assert_ne!(part_a, part_b);
+    }
+
+    #[test]
+    fn stale_parts_spare_kept_and_active_ones() {
+        let root = ScratchDir::new("atomic-stale");
+        std::fs::create_dir_all(root.join("sub")).unwrap();
+
+        let stale = part_path(&root.join("sub/viejo"));
+        let kept = part_path(&root.join("diario"));
+        let active = part_path(&root.join("en-curso"));
+        for part in [&stale, &kept, &active] {
+            std::fs::write(part, b"datos").unwrap();
+        }
+        std::fs::write(root.join("normal"), b"datos").unwrap();
+
+        mark_active(&active);
+        let freed = remove_stale_parts(&root, &HashSet::from([kept.clone()]));
+        mark_inactive(&active);
+
+        assert_eq!(freed, 5);
+        assert!(!stale.exists());
+        assert!(kept.exists() && active.exists() && root.join("normal").exists());
+    }
+}
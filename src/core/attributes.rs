@@ -0,0 +1,185 @@
+// src/core/attributes.rs
+use std::ffi::CString;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+
+use nix::errno::Errno;
+use nix::libc;
+use nix::sys::stat::{fchmodat, utimensat, FchmodatFlags, Mode, UtimensatFlags};
+use nix::sys::time::TimeSpec;
+use nix::unistd::{fchownat, FchownatFlags, Gid, Uid};
+
+/// Copia propietario, permisos, xattrs/ACLs y timestamps de `source` a `dest`.
+///
+/// Nunca falla: cada atributo que no se pueda aplicar se devuelve como aviso.
+/// Los enlaces simbólicos se tratan sin seguirlos.
+pub fn copy_attributes(source: &Path, dest: &Path) -> Vec<String> {
+    let mut warnings = Vec::new();
+
+    // Si el destino no es un enlace, el origen se copió siguiendo el enlace
+    let dest_is_symlink = std::fs::symlink_metadata(dest)
+        .map(|m| m.file_type().is_symlink())
+        .unwrap_or(false);
+    let source = match dest_is_symlink {
+        true => source.to_path_buf(),
+        false => std::fs::canonicalize(source).unwrap_or_else(|_| source.to_path_buf()),
+    };
+    let source = source.as_path();
+
+    let metadata = match std::fs::symlink_metadata(source) {
+        Ok(metadata) => metadata,
+        Err(e) => {
+            warnings.push(format!("{}: no se pudieron leer los atributos: {}", source.display(), e));
+            return warnings;
+        }
+    };
+    let is_symlink = metadata.file_type().is_symlink();
+
+    // El orden importa: chown borra los bits setuid/setgid, chmod reescribe la
+    // máscara de la ACL y cualquier escritura actualiza los timestamps.
+    if let Err(e) = copy_ownership(dest, &metadata) {
+        warnings.push(format!("{}: no se pudo cambiar el propietario: {}", dest.display(), e));
+    }
+
+    if !is_symlink {
+        let mode = Mode::from_bits_truncate(metadata.mode() as libc::mode_t);
+        if let Err(e) = fchmodat(None, dest, mode, FchmodatFlags::FollowSymlink) {
+            warnings.push(format!("{}: no se pudieron aplicar los permisos: {}", dest.display(), e));
+        }
+    }
+
+    warnings.extend(copy_xattrs(source, dest, is_symlink));
+
+    let atime = TimeSpec::new(metadata.atime(), metadata.atime_nsec());
+    let mtime = TimeSpec::new(metadata.mtime(), metadata.mtime_nsec());
+    if let Err(e) = utimensat(None, dest, &atime, &mtime, UtimensatFlags::NoFollowSymlink) {
+        warnings.push(format!("{}: no se pudieron aplicar las fechas: {}", dest.display(), e));
+    }
+
+    warnings
+}
+
+/// Sin privilegios solo se puede cambiar el grupo a uno al que pertenezcamos,
+/// así que en ese caso los fallos no se consideran avisos.
+fn copy_ownership(dest: &Path, metadata: &std::fs::Metadata) -> nix::Result<()> {
+    let uid = Uid::from_raw(metadata.uid());
+    let gid = Gid::from_raw(metadata.gid());
+
+    if Uid::effective().is_root() {
+        return fchownat(None, dest, Some(uid), Some(gid), FchownatFlags::NoFollowSymlink);
+    }
+
+    match fchownat(None, dest, None, Some(gid), FchownatFlags::NoFollowSymlink) {
+        Err(Errno::EPERM) => Ok(()),
+        other => other,
+    }
+}
+
+/// Copiar los atributos extendidos. Las ACL POSIX viajan como
+/// `system.posix_acl_access` y `system.posix_acl_default`.
+fn copy_xattrs(source: &Path, dest: &Path, is_symlink: bool) -> Vec<String> {
+    let mut warnings = Vec::new();
+
+    let names = match list_xattrs(source) {
+        Ok(names) => names,
+        // El sistema de archivos de origen no soporta xattrs: no hay nada que copiar
+        Err(Errno::ENOTSUP) => return warnings,
+        Err(e) => {
+            warnings.push(format!("{}: no se pudieron listar los xattrs: {}", source.display(), e));
+            return warnings;
+        }
+    };
+
+    for name in names {
+        let result = get_xattr(source, &name).and_then(|value| set_xattr(dest, &name, &value));
+
+        match result {
+            Ok(()) => {}
+            // Linux no permite xattrs user.* en enlaces simbólicos
+            Err(Errno::EPERM) if is_symlink => {}
+            Err(e) => warnings.push(format!(
+                "{}: no se pudo copiar el xattr {}: {}",
+                dest.display(),
+                name.to_string_lossy(),
+                e
+            )),
+        }
+    }
+
+    warnings
+}
+
+fn path_cstring(path: &Path) -> nix::Result<CString> {
+    CString::new(path.as_os_str().as_bytes()).map_err(|_| Errno::EINVAL)
+}
+
+fn list_xattrs(path: &Path) -> nix::Result<Vec<CString>> {
+    let c_path = path_cstring(path)?;
+
+    loop {
+        let size = unsafe { libc::llistxattr(c_path.as_ptr(), std::ptr::null_mut(), 0) };
+        if size < 0 {
+            return Err(Errno::last());
+        }
+        if size == 0 {
+            return Ok(Vec::new());
+        }
+
+        let mut buffer = vec![0u8; size as usize];
+        let read = unsafe { libc::llistxattr(c_path.as_ptr(), buffer.as_mut_ptr() as *mut libc::c_char, buffer.len()) };
+        if read < 0 {
+            // La lista creció entre las dos llamadas
+            if Errno::last() == Errno::ERANGE {
+                continue;
+            }
+            return Err(Errno::last());
+        }
+
+        buffer.truncate(read as usize);
+        return Ok(buffer
+            .split(|b| *b == 0)
+            .filter(|name| !name.is_empty())
+            .filter_map(|name| CString::new(name).ok())
+            .collect());
+    }
+}
+
+fn get_xattr(path: &Path, name: &CString) -> nix::Result<Vec<u8>> {
+    let c_path = path_cstring(path)?;
+
+    loop {
+        let size = unsafe { libc::lgetxattr(c_path.as_ptr(), name.as_ptr(), std::ptr::null_mut(), 0) };
+        if size < 0 {
+            return Err(Errno::last());
+        }
+
+        let mut value = vec![0u8; size as usize];
+        let read = unsafe {
+            libc::lgetxattr(c_path.as_ptr(), name.as_ptr(), value.as_mut_ptr() as *mut libc::c_void, value.len())
+        };
+        if read < 0 {
+            if Errno::last() == Errno::ERANGE {
+                continue;
+            }
+            return Err(Errno::last());
+        }
+
+        value.truncate(read as usize);
+        return Ok(value);
+    }
+}
+
+fn set_xattr(path: &Path, name: &CString, value: &[u8]) -> nix::Result<()> {
+    let c_path = path_cstring(path)?;
+    let result = unsafe {
+        libc::lsetxattr(c_path.as_ptr(), name.as_ptr(), value.as_ptr() as *const libc::c_void, value.len(), 0)
+    };
+
+    if result < 0 {
+        Err(Errno::last())
+    } else {
+        Ok(())
+    }
+}
+
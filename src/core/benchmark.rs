@@ -0,0 +1,178 @@
+// src/core/benchmark.rs
+// Velocidad medida de cada dispositivo: una prueba rápida opcional y lo
+// aprendido de las transferencias reales, guardado por identificador de dispositivo
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+use std::os::unix::fs::FileExt;
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::core::error_recovery::{CopyError, IoOperation, IoResultExt};
+
+const BYTES_PER_MB: f64 = 1_000_000.0;
+
+/// Tamaño del archivo de prueba y de cada escritura secuencial
+const BENCH_FILE_SIZE: u64 = 64 * 1024 * 1024;
+const SEQUENTIAL_BLOCK: usize = 1024 * 1024;
+
+/// Accesos aleatorios de 4 KiB en cada sentido
+const RANDOM_BLOCK: usize = 4096;
+const RANDOM_OPS: usize = 256;
+
+/// Lo que hace falta para que una transferencia enseñe algo: por debajo
+/// dominan la apertura de archivos y la caché
+const MIN_SAMPLE_BYTES: u64 = 64 * 1024 * 1024;
+const MIN_SAMPLE_DURATION: Duration = Duration::from_secs(2);
+
+/// Peso de cada muestra nueva en la media móvil
+const SAMPLE_WEIGHT: f64 = 0.3;
+
+/// Velocidades conocidas de un dispositivo, en MB/s
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DeviceSpeed {
+    #[serde(default)]
+    pub sequential_read_mbps: Option<f64>,
+    #[serde(default)]
+    pub sequential_write_mbps: Option<f64>,
+    #[serde(default)]
+    pub random_read_mbps: Option<f64>,  // Bloques de 4 KiB
+    #[serde(default)]
+    pub random_write_mbps: Option<f64>,
+    #[serde(default)]
+    pub observed_mbps: Option<f64>,     // Media móvil de transferencias reales
+    #[serde(default)]
+    pub samples: u32,
+    #[serde(default)]
+    pub updated_at: u64,                // Segundos desde la época UNIX
+}
+
+impl DeviceSpeed {
+    /// Velocidad para el optimizador: la de la prueba si se hizo, si no la observada
+    pub fn estimated_mbps(&self) -> Option<f64> {
+        match (self.sequential_read_mbps, self.sequential_write_mbps) {
+            (Some(read), Some(write)) => Some(read.min(write)),
+            (read, write) => read.or(write).or(self.observed_mbps),
+        }
+    }
+
+    /// Incorporar el rendimiento de una transferencia real
+    pub fn record_sample(&mut self, mbps: f64) {
+        self.observed_mbps = Some(match self.observed_mbps {
+            Some(previous) => previous + SAMPLE_WEIGHT * (mbps - previous),
+            None => mbps,
+        });
+        self.samples += 1;
+        self.touch();
+    }
+
+    fn touch(&mut self) {
+        self.updated_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or(0);
+    }
+}
+
+/// Rendimiento de una transferencia terminada, si es lo bastante larga para fiarse
+pub fn transfer_throughput(bytes: u64, duration: Duration) -> Option<f64> {
+    if bytes < MIN_SAMPLE_BYTES || duration < MIN_SAMPLE_DURATION {
+        return None;
+    }
+    Some(bytes as f64 / BYTES_PER_MB / duration.as_secs_f64())
+}
+
+/// Archivo de prueba oculto que se borra pase lo que pase
+struct BenchFile {
+    path: PathBuf,
+}
+
+impl Drop for BenchFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Vaciar de la caché de páginas lo leído o escrito, para medir el disco
+fn drop_cache(file: &File, path: &Path) -> Result<(), CopyError> {
+    file.sync_all().io_context(IoOperation::Sync, path)?;
+    let _ = nix::fcntl::posix_fadvise(file.as_raw_fd(), 0, 0, nix::fcntl::PosixFadviseAdvice::POSIX_FADV_DONTNEED);
+    Ok(())
+}
+
+fn mbps(bytes: u64, elapsed: Duration) -> Option<f64> {
+    let secs = elapsed.as_secs_f64();
+    (secs > 0.0).then(|| bytes as f64 / BYTES_PER_MB / secs)
+}
+
+/// Prueba rápida en un archivo temporal dentro de `dir`: escritura y lectura
+/// secuenciales de 64 MiB y accesos aleatorios de 4 KiB. Bloquea varios
+/// segundos en discos lentos; llamar desde `spawn_blocking`.
+pub fn run_quick_benchmark(dir: &Path) -> Result<DeviceSpeed, CopyError> {
+    let bench = BenchFile {
+        path: dir.join(format!(".copymaster-bench-{}", std::process::id())),
+    };
+    let path = bench.path.as_path();
+
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create_new(true)
+        .open(path)
+        .io_context(IoOperation::Create, path)?;
+
+    let mut rng = rand::thread_rng();
+    let mut block = vec![0u8; SEQUENTIAL_BLOCK];
+    rng.fill(&mut block[..]);
+
+    let mut speed = DeviceSpeed::default();
+
+    // Escritura secuencial, contando hasta que los datos están en el disco
+    let started = Instant::now();
+    let mut written = 0;
+    while written < BENCH_FILE_SIZE {
+        file.write_all(&block).io_context(IoOperation::Write, path)?;
+        written += SEQUENTIAL_BLOCK as u64;
+    }
+    drop_cache(&file, path)?;
+    speed.sequential_write_mbps = mbps(written, started.elapsed());
+
+    // Lectura secuencial
+    let mut reader = File::open(path).io_context(IoOperation::Open, path)?;
+    let started = Instant::now();
+    let mut read = 0;
+    loop {
+        let n = reader.read(&mut block).io_context(IoOperation::Read, path)?;
+        if n == 0 {
+            break;
+        }
+        read += n as u64;
+    }
+    speed.sequential_read_mbps = mbps(read, started.elapsed());
+    drop_cache(&reader, path)?;
+
+    // Lecturas aleatorias
+    let blocks = BENCH_FILE_SIZE / RANDOM_BLOCK as u64;
+    let mut small = vec![0u8; RANDOM_BLOCK];
+    let started = Instant::now();
+    for _ in 0..RANDOM_OPS {
+        let offset = rng.gen_range(0..blocks) * RANDOM_BLOCK as u64;
+        reader.read_exact_at(&mut small, offset).io_context(IoOperation::Read, path)?;
+    }
+    speed.random_read_mbps = mbps((RANDOM_OPS * RANDOM_BLOCK) as u64, started.elapsed());
+
+    // Escrituras aleatorias, hasta que llegan al disco
+    let started = Instant::now();
+    for _ in 0..RANDOM_OPS {
+        let offset = rng.gen_range(0..blocks) * RANDOM_BLOCK as u64;
+        file.write_all_at(&small, offset).io_context(IoOperation::Write, path)?;
+    }
+    drop_cache(&file, path)?;
+    speed.random_write_mbps = mbps((RANDOM_OPS * RANDOM_BLOCK) as u64, started.elapsed());
+
+    speed.touch();
+    Ok(speed)
+}
@@ -14,6 +14,28 @@ pub struct AppConfig {
     pub window_state: WindowState,
     // NUEVO: Configuración de conflictos global
     pub conflict_resolution: GlobalConflictResolution,
+    #[serde(default)]
+    pub bandwidth_limit_mbps: Option<f64>, // Límite global fijo
+    #[serde(default)]
+    pub bandwidth_schedules: Vec<crate::core::throttle::BandwidthSchedule>,
+    #[serde(default = "default_ssd_parallel_transfers")]
+    pub ssd_parallel_transfers: usize, // Transferencias simultáneas por SSD
+    #[serde(default = "default_true")]
+    pub hotplug_uevents: bool, // Escuchar los uevents del kernel además de la tabla de montajes
+    #[serde(default)]
+    pub device_speeds: std::collections::HashMap<String, crate::core::benchmark::DeviceSpeed>, // Por UUID o etiqueta
+    #[serde(default)]
+    pub retry_policies: crate::core::error_recovery::RetryPolicies, // Reintentos por familia de error
+    #[serde(default)]
+    pub unattended_error_action: UnattendedErrorAction, // Sin interfaz (daemon, CLI) a quien preguntar
+}
+
+fn default_ssd_parallel_transfers() -> usize {
+    2
+}
+
+fn default_true() -> bool {
+    true
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -50,6 +72,14 @@ impl Default for GlobalConflictResolution {
     }
 }
 
+/// Qué hacer con un error sin resolver cuando no hay a quién preguntar
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+pub enum UnattendedErrorAction {
+    #[default]
+    Skip,  // Anotar el error y seguir con el resto
+    Abort, // Parar la transferencia en el primer error
+}
+
 // NUEVO ENUM: Acciones para conflictos
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum ConflictAction {
@@ -79,6 +109,13 @@ impl Default for AppConfig {
                 maximized: false,
             },
             conflict_resolution: GlobalConflictResolution::default(),
+            bandwidth_limit_mbps: None,
+            bandwidth_schedules: Vec::new(),
+            ssd_parallel_transfers: default_ssd_parallel_transfers(),
+            hotplug_uevents: true,
+            device_speeds: std::collections::HashMap::new(),
+            retry_policies: crate::core::error_recovery::RetryPolicies::default(),
+            unattended_error_action: UnattendedErrorAction::default(),
         }
     }
 }
@@ -89,10 +126,14 @@ pub struct ConfigManager {
 }
 
 impl ConfigManager {
+    /// Directorio de configuración de CopyMaster (~/.config/copymaster)
+    pub fn config_dir() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("copymaster"))
+    }
+    
     pub fn new() -> Result<Self, Box<dyn std::error::Error>> {
-        let config_dir = dirs::config_dir()
-            .ok_or("No se pudo encontrar directorio de configuración")?
-            .join("copymaster");
+        let config_dir = Self::config_dir()
+            .ok_or("No se pudo encontrar directorio de configuración")?;
         
         if !config_dir.exists() {
             fs::create_dir_all(&config_dir)?;
@@ -158,6 +199,16 @@ impl ConfigManager {
         Ok(())
     }
     
+    /// Modificar y guardar la velocidad conocida de un dispositivo
+    pub fn update_device_speed(
+        &mut self,
+        device_id: &str,
+        update: impl FnOnce(&mut crate::core::benchmark::DeviceSpeed),
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        update(self.config.device_speeds.entry(device_id.to_string()).or_default());
+        self.save_config()
+    }
+    
     pub fn get_device_preference(&self, device_id: &str) -> Option<&DevicePreference> {
         self.config.remembered_devices
             .iter()
@@ -0,0 +1,110 @@
+// src/core/control.rs
+// Pausa, reanudación y cancelación cooperativas de las copias
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+use crate::core::error_recovery::CopyError;
+
+/// Cada cuánto se comprueba si una copia en pausa debe continuar
+const PAUSE_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Control de una copia, de una cola de dispositivo o de todas a la vez.
+///
+/// Los controles forman una jerarquía (global > cola > job): pausar un nivel
+/// pausa todo lo que cuelga de él. Una cancelación de un nivel superior solo
+/// afecta a los controles que ya existían, de modo que lo que se encole
+/// después de un "cancelar todo" se copia con normalidad.
+#[derive(Debug, Default)]
+pub struct TransferControl {
+    paused: AtomicBool,
+    cancels: AtomicU64,
+    // Alguna copia llegó a esperar en pausa con este control
+    waited: AtomicBool,
+    // Cada ancestro con el número de cancelaciones que tenía al crear este control
+    ancestors: Vec<(Arc<TransferControl>, u64)>,
+}
+
+impl TransferControl {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self::default())
+    }
+
+    /// Control que además obedece a `parent` y a todos sus ancestros
+    pub fn child(parent: &Arc<Self>) -> Arc<Self> {
+        let ancestors = parent.ancestors
+            .iter()
+            .map(|(ancestor, _)| ancestor)
+            .chain(std::iter::once(parent))
+            .map(|ancestor| (ancestor.clone(), ancestor.cancels.load(Ordering::SeqCst)))
+            .collect();
+
+        Arc::new(Self {
+            ancestors,
+            ..Self::default()
+        })
+    }
+
+    pub fn pause(&self) {
+        self.paused.store(true, Ordering::SeqCst);
+    }
+
+    pub fn resume(&self) {
+        self.paused.store(false, Ordering::SeqCst);
+    }
+
+    pub fn cancel(&self) {
+        self.cancels.fetch_add(1, Ordering::SeqCst);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+            || self.ancestors.iter().any(|(ancestor, _)| ancestor.paused.load(Ordering::SeqCst))
+    }
+
+    /// Si alguna copia ha llegado a detenerse en pausa con este control
+    pub fn was_paused(&self) -> bool {
+        self.waited.load(Ordering::SeqCst)
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancels.load(Ordering::SeqCst) > 0
+            || self.ancestors.iter().any(|(ancestor, seen)| ancestor.cancels.load(Ordering::SeqCst) > *seen)
+    }
+
+    /// Punto de control entre chunks: espera mientras haya pausa, con los
+    /// archivos abiertos, y devuelve `Cancelled` si hay que abandonar.
+    /// Solo debe llamarse desde hilos bloqueantes.
+    pub fn checkpoint(&self) -> Result<(), CopyError> {
+        loop {
+            if self.is_cancelled() {
+                return Err(CopyError::Cancelled);
+            }
+            if !self.is_paused() {
+                return Ok(());
+            }
+            self.waited.store(true, Ordering::SeqCst);
+            std::thread::sleep(PAUSE_POLL_INTERVAL);
+        }
+    }
+
+    /// Como `checkpoint`, para usar entre archivos desde el runtime async
+    pub async fn checkpoint_async(&self) -> Result<(), CopyError> {
+        loop {
+            if self.is_cancelled() {
+                return Err(CopyError::Cancelled);
+            }
+            if !self.is_paused() {
+                return Ok(());
+            }
+            self.waited.store(true, Ordering::SeqCst);
+            tokio::time::sleep(PAUSE_POLL_INTERVAL).await;
+        }
+    }
+}
+
+/// Control global, raíz de los de cada cola
+pub fn global_control() -> Arc<TransferControl> {
+    static GLOBAL: OnceLock<Arc<TransferControl>> = OnceLock::new();
+    GLOBAL.get_or_init(TransferControl::new).clone()
+}
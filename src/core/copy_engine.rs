@@ -11,6 +11,50 @@ pub enum CopyAlgorithm {
     Verified,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum VerificationMode {
+    /// Hashear origen y destino mientras se copia
+    #[default]
+    Streaming,
+    /// Hacer fsync y releer el destino desde disco al terminar
+    ReadBack,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum SymlinkPolicy {
+    /// Recrear el enlace en el destino
+    #[default]
+    CopyAsLink,
+    /// Copiar el archivo o directorio al que apunta
+    Dereference,
+    /// No copiar enlaces
+    Skip,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum SyncMode {
+    /// Escrituras con caché; se sincroniza al cerrar
+    #[default]
+    None,
+    /// O_DSYNC: cada escritura espera a los datos
+    Data,
+    /// O_SYNC: cada escritura espera a datos y metadatos
+    Full,
+}
+
+/// Cómo se copió realmente cada archivo
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CopyMethod {
+    /// std::fs::copy
+    Standard,
+    /// Clon de extents (FICLONE) en btrfs/XFS
+    Reflink,
+    /// copy_file_range dentro del kernel
+    CopyFileRange,
+    /// Lectura/escritura por chunks en espacio de usuario
+    Chunked,
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub enum ConflictResolution {
     Overwrite,
@@ -26,10 +70,34 @@ pub struct CopyOptions {
     pub buffer_size: usize,        // 64KB por defecto
     pub max_threads: usize,        // Núcleos * 2
     pub verify_after_copy: bool,
+    #[serde(default)]
+    pub verification_mode: VerificationMode,
     pub conflict_resolution: ConflictResolution,
-    pub preserve_attributes: bool, // permisos, timestamps
+    pub preserve_attributes: bool, // permisos, propietario, timestamps, xattrs y ACLs
     pub sparse_files: bool,        // Archivos sparse
     pub sync_io: bool,            // O_SYNC para mayor seguridad
+    #[serde(default)]
+    pub sync_mode: SyncMode,      // Más fino que sync_io: O_DSYNC u O_SYNC
+    #[serde(default)]
+    pub direct_io: bool,          // O_DIRECT, sin pasar por la caché de páginas
+    #[serde(default)]
+    pub symlink_policy: SymlinkPolicy,
+    #[serde(default = "default_true")]
+    pub preserve_hardlinks: bool, // Mantener enlazados los archivos con varios nombres
+    #[serde(default = "default_true")]
+    pub kernel_acceleration: bool, // reflink y copy_file_range cuando sea posible
+    #[serde(default)]
+    pub throttle_mbps: Option<f64>, // Límite de ancho de banda del motor
+    #[serde(default)]
+    pub keep_partial_on_cancel: bool, // Conservar el temporal al cancelar para poder reanudar
+    #[serde(default = "default_true")]
+    pub adaptive_tuning: bool, // Ajustar buffer e hilos durante la copia según el rendimiento
+    #[serde(default = "default_true")]
+    pub interleave_io: bool, // Leer por adelantado el siguiente lote mientras se escribe el actual
+}
+
+fn default_true() -> bool {
+    true
 }
 
 impl Default for CopyOptions {
@@ -39,10 +107,30 @@ impl Default for CopyOptions {
             buffer_size: 65536,
             max_threads: num_cpus::get() * 2,
             verify_after_copy: true,
+            verification_mode: VerificationMode::Streaming,
             conflict_resolution: ConflictResolution::AskUser,
             preserve_attributes: true,
             sparse_files: true,
             sync_io: false,
+            sync_mode: SyncMode::None,
+            direct_io: false,
+            symlink_policy: SymlinkPolicy::CopyAsLink,
+            preserve_hardlinks: true,
+            kernel_acceleration: true,
+            throttle_mbps: None,
+            keep_partial_on_cancel: false,
+            adaptive_tuning: true,
+            interleave_io: true,
+        }
+    }
+}
+
+impl CopyOptions {
+    /// Modo de sincronización efectivo; `sync_io` equivale a O_SYNC
+    pub fn effective_sync_mode(&self) -> SyncMode {
+        match self.sync_mode {
+            SyncMode::None if self.sync_io => SyncMode::Full,
+            mode => mode,
         }
     }
 }
@@ -50,13 +138,20 @@ impl Default for CopyOptions {
 #[derive(Debug, Clone)]
 pub struct CopyStats {
     pub bytes_transferred: u64,
-    pub total_bytes: u64,
+    pub total_bytes: u64,   // Bytes que hay que escribir (sin huecos)
+    pub logical_bytes: u64, // Tamaño aparente de los archivos
     pub speed_bps: f64,
     pub elapsed_time: std::time::Duration,
     pub estimated_remaining: std::time::Duration,
     pub files_copied: usize,
     pub files_total: usize,
     pub errors: Vec<String>,
+    pub verified_hash: Option<u128>, // xxh3-128 del último archivo verificado
+    pub started_at: Option<std::time::Instant>,
+    pub file_methods: Vec<(PathBuf, CopyMethod)>, // Método usado para cada archivo
+    pub tuning: Option<crate::core::optimizer::TuningReport>, // Parámetros elegidos por el ajuste adaptativo
+    pub retries: Vec<crate::core::error_recovery::RetryAttempt>, // Fallos que se volvieron a intentar
+    pub active_time: std::time::Duration, // Solo los intentos que acabaron bien, sin esperas entre ellos
 }
 
 impl CopyStats {
@@ -64,14 +159,45 @@ impl CopyStats {
         Self {
             bytes_transferred: 0,
             total_bytes: 0,
+            logical_bytes: 0,
             speed_bps: 0.0,
             elapsed_time: std::time::Duration::from_secs(0),
             estimated_remaining: std::time::Duration::from_secs(0),
             files_copied: 0,
             files_total: 0,
             errors: Vec::new(),
+            verified_hash: None,
+            started_at: None,
+            file_methods: Vec::new(),
+            tuning: None,
+            retries: Vec::new(),
+            active_time: std::time::Duration::from_secs(0),
+        }
+    }
+
+    /// Registrar bytes escritos y recalcular velocidad y tiempo restante.
+    /// El tiempo se mide desde el primer registro, aunque abarque varios archivos.
+    pub fn record_bytes(&mut self, bytes: u64) {
+        let elapsed = self.started_at.get_or_insert_with(std::time::Instant::now).elapsed();
+        self.bytes_transferred += bytes;
+        self.elapsed_time = elapsed;
+
+        let secs = elapsed.as_secs_f64();
+        if secs > 0.0 {
+            self.speed_bps = self.bytes_transferred as f64 / secs;
+        }
+
+        if self.speed_bps > 0.0 {
+            let remaining = self.total_bytes.saturating_sub(self.bytes_transferred);
+            self.estimated_remaining = std::time::Duration::from_secs_f64(remaining as f64 / self.speed_bps);
         }
     }
+    
+    /// Añadir un aviso que no impide completar la copia
+    pub fn add_warning(&mut self, message: String) {
+        log::warn!("{}", message);
+        self.errors.push(format!("Aviso: {}", message));
+    }
 }
 
 // NUEVA ESTRUCTURA: Configuración de conflictos en tiempo de ejecución
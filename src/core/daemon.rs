@@ -20,6 +20,7 @@ pub enum DaemonNotification {
     NewDropEvent(DropEvent),
 }
 
+#[derive(Debug)]
 pub struct DropEvent {
     pub device_id: String,
     pub items: Vec<crate::core::drag_drop::DroppedItem>,
@@ -38,6 +39,12 @@ impl CopyMasterDaemon {
         }
     }
     
+    /// Compartir el gestor de transferencias con la interfaz
+    pub fn with_transfer_manager(mut self, transfer_manager: Arc<Mutex<crate::core::transfer_manager::TransferManager>>) -> Self {
+        self.transfer_manager = transfer_manager;
+        self
+    }
+    
     pub async fn start(&mut self) -> Result<(), Box<dyn std::error::Error>> {
         self.is_running = true;
         
@@ -1,8 +1,19 @@
 // src/core/device_detector.rs
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::time::Duration;
 use nix::sys::statvfs::statvfs;
 
+use crate::core::daemon::DaemonNotification;
+
+/// Profundidad máxima al seguir `slaves/` de dm, md y similares
+const MAX_STACK_DEPTH: usize = 8;
+
+/// Cada cuánto se relee la tabla de montajes aunque no llegue ningún aviso
+const MOUNT_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
 #[derive(Debug, Clone)]
 pub enum DeviceType {
     Unknown,
@@ -29,81 +40,516 @@ pub struct DeviceInfo {
     pub is_removable: bool,
     pub is_read_only: bool,
     pub estimated_speed_mbps: Option<f64>,
+    pub device_id: Option<String>, // UUID o etiqueta del sistema de archivos
+}
+
+/// Una línea de /proc/self/mountinfo
+#[derive(Debug, Clone)]
+pub struct MountEntry {
+    pub major: u64,
+    pub minor: u64,
+    pub mount_point: PathBuf,
+    pub filesystem: String,
+    pub source: String,
+    pub read_only: bool,
+}
+
+impl MountEntry {
+    /// Formato: `id padre maj:min raíz punto opciones [opcionales...] - tipo origen superopciones`
+    /// Montajes de dispositivos de bloque reales, los que pueden conectarse y desconectarse
+    pub fn is_block_device(&self) -> bool {
+        self.source.starts_with("/dev/") && !self.source.starts_with("/dev/loop")
+    }
+
+    fn parse(line: &str) -> Option<Self> {
+        let (left, right) = line.split_once(" - ")?;
+        let left: Vec<&str> = left.split_whitespace().collect();
+        let mut right = right.split_whitespace();
+
+        let (major, minor) = left.get(2)?.split_once(':')?;
+        let mount_options = left.get(5)?;
+        let filesystem = right.next()?.to_string();
+        let source = right.next().map(unescape).unwrap_or_default();
+        let super_options = right.next().unwrap_or("");
+
+        Some(Self {
+            major: major.parse().ok()?,
+            minor: minor.parse().ok()?,
+            mount_point: PathBuf::from(unescape(left.get(4)?)),
+            filesystem,
+            source,
+            read_only: has_ro(mount_options) || has_ro(super_options),
+        })
+    }
+}
+
+fn has_ro(options: &str) -> bool {
+    options.split(',').any(|option| option == "ro")
+}
+
+/// El kernel escapa espacio, tabulador, salto de línea y barra como `\ooo`
+fn unescape(field: &str) -> String {
+    let bytes = field.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let octal = bytes.get(i + 1..i + 4)
+            .filter(|_| bytes[i] == b'\\')
+            .and_then(|digits| std::str::from_utf8(digits).ok())
+            .and_then(|digits| u8::from_str_radix(digits, 8).ok());
+
+        match octal {
+            Some(byte) => {
+                out.push(byte);
+                i += 4;
+            }
+            None => {
+                out.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Leer un atributo de sysfs sin espacios finales
+fn read_attr(path: &Path) -> Option<String> {
+    std::fs::read_to_string(path).ok().map(|value| value.trim().to_string())
+}
+
+/// Ruta canónica aunque el final aún no exista (p. ej. la carpeta de destino)
+pub fn resolve_path(path: &Path) -> PathBuf {
+    let mut existing = path;
+    let mut missing = Vec::new();
+
+    while std::fs::symlink_metadata(existing).is_err() {
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                missing.push(name.to_os_string());
+                existing = parent;
+            }
+            _ => return path.to_path_buf(),
+        }
+    }
+
+    let mut resolved = std::fs::canonicalize(existing).unwrap_or_else(|_| existing.to_path_buf());
+    resolved.extend(missing.iter().rev());
+    resolved
 }
 
 pub struct DeviceMonitor {
     known_devices: Vec<DeviceInfo>,
+    proc_root: PathBuf,
+    sys_root: PathBuf,
+    dev_root: PathBuf,
+    resolve_paths: bool, // Resolver enlaces en el disco real; no con un árbol falso
+    uevents: bool, // Escuchar también los uevents del kernel por netlink
+    speeds: HashMap<String, crate::core::benchmark::DeviceSpeed>, // Velocidades medidas por `device_id`
 }
 
 impl DeviceMonitor {
     pub fn new() -> Self {
         Self {
             known_devices: Vec::new(),
+            proc_root: PathBuf::from("/proc"),
+            sys_root: PathBuf::from("/sys"),
+            dev_root: PathBuf::from("/dev"),
+            resolve_paths: true,
+            uevents: false,
+            speeds: HashMap::new(),
         }
     }
 
-    pub async fn start_monitoring(&mut self, notification_channel: tokio::sync::mpsc::Sender<crate::core::daemon::DaemonNotification>) {
-        // Implementar monitoreo de dispositivos (usando udev, dbus, etc.)
-        // Por simplicidad, no implementamos el monitoreo completo aquí.
+    /// Velocidades medidas o aprendidas, normalmente `AppConfig::device_speeds`;
+    /// sustituyen a la estimación por tipo de dispositivo
+    pub fn with_speed_cache(mut self, speeds: HashMap<String, crate::core::benchmark::DeviceSpeed>) -> Self {
+        self.speeds = speeds;
+        self
+    }
+
+    /// Leer mountinfo y sysfs bajo otras raíces, para pruebas con un árbol falso.
+    /// Las rutas que se consultan son entonces del árbol falso y se comparan tal cual.
+    pub fn with_roots(mut self, proc_root: impl Into<PathBuf>, sys_root: impl Into<PathBuf>) -> Self {
+        self.proc_root = proc_root.into();
+        self.sys_root = sys_root.into();
+        self.resolve_paths = false;
+        self
+    }
+
+    /// Directorio con `disk/by-uuid` y `disk/by-label`
+    pub fn with_dev_root(mut self, dev_root: impl Into<PathBuf>) -> Self {
+        self.dev_root = dev_root.into();
+        self
+    }
+
+    /// Además de vigilar la tabla de montajes, despertar con cada uevent de
+    /// bloque del kernel para detectar antes las desconexiones
+    pub fn with_uevents(mut self, enabled: bool) -> Self {
+        self.uevents = enabled;
+        self
+    }
+
+    pub fn known_devices(&self) -> &[DeviceInfo] {
+        &self.known_devices
+    }
+
+    /// Vigilar la tabla de montajes y avisar de cada dispositivo que aparece o
+    /// desaparece, identificado por `device_id`. Termina al cerrarse el canal.
+    pub async fn start_monitoring(&mut self, notification_channel: tokio::sync::mpsc::Sender<DaemonNotification>) {
+        let (wake_tx, mut wake_rx) = tokio::sync::mpsc::channel(1);
+        self.watch_mountinfo(wake_tx.clone());
+        if self.uevents {
+            self.listen_uevents(wake_tx);
+        }
+
+        let mut connected = self.connected_devices();
+        self.refresh_known_devices(&connected).await;
+
+        loop {
+            tokio::select! {
+                Some(()) = wake_rx.recv() => {}
+                _ = tokio::time::sleep(MOUNT_POLL_INTERVAL) => {}
+            }
+            // Al volver, los hilos de vigilancia ven el canal cerrado y terminan
+            if notification_channel.is_closed() {
+                return;
+            }
+
+            let current = self.connected_devices();
+            let mut events = Vec::new();
+
+            for id in connected.keys().filter(|id| !current.contains_key(*id)) {
+                log::info!("Dispositivo desconectado: {}", id);
+                events.push(DaemonNotification::DeviceDisconnected(id.clone()));
+            }
+            for (id, mount) in current.iter().filter(|(id, _)| !connected.contains_key(*id)) {
+                log::info!("Dispositivo conectado: {} en {}", id, mount.mount_point.display());
+                events.push(DaemonNotification::DeviceConnected(id.clone()));
+            }
+
+            if events.is_empty() {
+                continue;
+            }
+
+            self.refresh_known_devices(&current).await;
+            connected = current;
+
+            for event in events {
+                if notification_channel.send(event).await.is_err() {
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Dispositivos montados ahora mismo, por su identificador estable
+    pub fn connected_devices(&self) -> HashMap<String, MountEntry> {
+        let mut devices = HashMap::new();
+
+        for mount in self.mounts().into_iter().filter(MountEntry::is_block_device) {
+            // Un mismo dispositivo puede estar montado varias veces (bind mounts)
+            devices.entry(self.device_id(&mount)).or_insert(mount);
+        }
+
+        devices
+    }
+
+    async fn refresh_known_devices(&mut self, connected: &HashMap<String, MountEntry>) {
+        let mut known = Vec::with_capacity(connected.len());
+        for mount in connected.values() {
+            known.push(self.detect_device(&mount.mount_point).await);
+        }
+        self.known_devices = known;
+    }
+
+    /// Identificador estable del sistema de archivos montado: el UUID, la
+    /// etiqueta o, si no tiene ninguno, el dispositivo de origen
+    pub fn device_id(&self, mount: &MountEntry) -> String {
+        let Some(name) = self.source_name(mount) else {
+            return mount.source.clone();
+        };
+
+        ["by-uuid", "by-label"]
+            .iter()
+            .find_map(|kind| {
+                std::fs::read_dir(self.dev_root.join("disk").join(kind))
+                    .ok()?
+                    .flatten()
+                    .find(|entry| {
+                        std::fs::read_link(entry.path())
+                            .map(|target| target.file_name() == Some(name.as_os_str()))
+                            .unwrap_or(false)
+                    })
+                    .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            })
+            .unwrap_or_else(|| mount.source.clone())
+    }
+
+    /// Nombre del dispositivo de origen siguiendo sus enlaces (/dev/mapper/x es dm-0)
+    fn source_name(&self, mount: &MountEntry) -> Option<std::ffi::OsString> {
+        let source = Path::new(&mount.source);
+
+        source.strip_prefix("/dev")
+            .ok()
+            .and_then(|relative| std::fs::canonicalize(self.dev_root.join(relative)).ok())
+            .and_then(|path| path.file_name().map(|name| name.to_os_string()))
+            .or_else(|| source.file_name().map(|name| name.to_os_string()))
+    }
+
+    /// El kernel marca mountinfo con POLLPRI cada vez que cambia la tabla de montajes
+    fn watch_mountinfo(&self, wake: tokio::sync::mpsc::Sender<()>) {
+        let Ok(file) = std::fs::File::open(self.proc_root.join("self/mountinfo")) else {
+            return;
+        };
+
+        std::thread::spawn(move || loop {
+            let mut fds = [nix::poll::PollFd::new(file.as_raw_fd(), nix::poll::PollFlags::POLLPRI)];
+            // Con tiempo de espera para notar que el monitor ya no existe
+            let changed = matches!(nix::poll::poll(&mut fds, 1000), Ok(n) if n > 0);
+
+            if wake.is_closed() {
+                break;
+            }
+            if changed {
+                let _ = wake.try_send(());
+            }
+        });
+    }
+
+    /// Uevents del kernel (grupo 1 de NETLINK_KOBJECT_UEVENT): un disco que se
+    /// quita sin desmontar aparece aquí antes que en la tabla de montajes
+    fn listen_uevents(&self, wake: tokio::sync::mpsc::Sender<()>) {
+        use nix::sys::socket::{bind, recv, socket, AddressFamily, MsgFlags, NetlinkAddr, SockFlag, SockProtocol, SockType};
+
+        let socket = socket(AddressFamily::Netlink, SockType::Datagram, SockFlag::SOCK_CLOEXEC, SockProtocol::NetlinkKObjectUEvent)
+            .and_then(|fd| bind(fd, &NetlinkAddr::new(0, 1)).map(|_| fd));
+
+        let fd = match socket {
+            Ok(fd) => fd,
+            Err(e) => {
+                log::warn!("Sin uevents del kernel, solo se vigila la tabla de montajes: {}", e);
+                return;
+            }
+        };
+
+        std::thread::spawn(move || {
+            let mut buffer = [0u8; 8192];
+
+            loop {
+                // Como con mountinfo: sin bloquear en recv, para terminar con el monitor
+                let mut fds = [nix::poll::PollFd::new(fd, nix::poll::PollFlags::POLLIN)];
+                let ready = match nix::poll::poll(&mut fds, 1000) {
+                    Ok(n) => n > 0,
+                    Err(nix::errno::Errno::EINTR) => false,
+                    Err(_) => break,
+                };
+
+                if wake.is_closed() {
+                    break;
+                }
+                if !ready {
+                    continue;
+                }
+
+                let len = match recv(fd, &mut buffer, MsgFlags::MSG_DONTWAIT) {
+                    Ok(len) => len,
+                    Err(nix::errno::Errno::EAGAIN | nix::errno::Errno::EINTR) => continue,
+                    // Se perdieron avisos: releer la tabla por si acaso
+                    Err(nix::errno::Errno::ENOBUFS) => {
+                        let _ = wake.try_send(());
+                        continue;
+                    }
+                    Err(_) => break,
+                };
+                // "ACCIÓN@ruta\0CLAVE=valor\0..."; solo interesan los de bloque
+                let message = String::from_utf8_lossy(&buffer[..len]);
+                if message.split('\0').any(|field| field == "SUBSYSTEM=block") {
+                    let _ = wake.try_send(());
+                }
+            }
+
+            let _ = nix::unistd::close(fd);
+        });
     }
 
     pub async fn detect_device(&mut self, path: &PathBuf) -> DeviceInfo {
-        // Detección básica
+        let mount = self.find_mount(path);
+
         let mut info = DeviceInfo {
             path: path.clone(),
             device_type: DeviceType::Unknown,
-            mount_point: self.get_mount_point(path).await,
-            filesystem: self.get_filesystem_type(path).await,
+            mount_point: mount.as_ref().map(|m| m.mount_point.clone()),
+            filesystem: mount.as_ref().map(|m| m.filesystem.clone()),
             block_size: 4096,
             total_space: 0,
             available_space: 0,
             is_removable: false,
             is_read_only: false,
             estimated_speed_mbps: None,
+            device_id: mount.as_ref().filter(|m| m.is_block_device()).map(|m| self.device_id(m)),
         };
 
-        if let Ok(stat) = statvfs(path) {
+        // El destino puede no existir aún; cuenta el sistema de archivos que lo contendrá
+        let existing = path.ancestors().find(|dir| dir.exists()).unwrap_or(path);
+        if let Ok(stat) = statvfs(existing) {
             info.block_size = stat.block_size() as u64;
-            info.total_space = stat.blocks() * stat.block_size();
-            info.available_space = stat.blocks_free() * stat.block_size();
+            info.total_space = stat.blocks() * stat.fragment_size();
+            // Sin los bloques reservados para root, que un usuario no puede usar
+            info.available_space = stat.blocks_available() * stat.fragment_size();
         }
 
         #[cfg(target_os = "linux")]
-        {
-            info.device_type = self.detect_linux_device_type(path).await;
-            info.is_removable = self.is_removable_linux(path).await;
-            info.is_read_only = self.is_read_only_linux(path).await;
+        if let Some(mount) = &mount {
+            let disk = self.block_device_dir(mount);
+            info.device_type = self.detect_linux_device_type(mount, disk.as_deref());
+            info.is_removable = self.is_removable_linux(&info.device_type, disk.as_deref());
+            info.is_read_only = self.is_read_only_linux(mount, disk.as_deref());
         }
 
-        info.estimated_speed_mbps = self.estimate_speed(&info.device_type);
+        info.estimated_speed_mbps = info.device_id.as_ref()
+            .and_then(|id| self.speeds.get(id))
+            .and_then(|speed| speed.estimated_mbps())
+            .or_else(|| self.estimate_speed(&info.device_type));
 
         info
     }
 
-    async fn get_mount_point(&self, path: &PathBuf) -> Option<PathBuf> {
-        // Implementar obtención del punto de montaje
-        None
+    /// Montajes actuales, en el orden de mountinfo
+    pub fn mounts(&self) -> Vec<MountEntry> {
+        std::fs::read_to_string(self.proc_root.join("self/mountinfo"))
+            .map(|content| content.lines().filter_map(MountEntry::parse).collect())
+            .unwrap_or_default()
     }
 
-    async fn get_filesystem_type(&self, path: &PathBuf) -> Option<String> {
-        // Implementar obtención del tipo de sistema de archivos
-        None
+    /// Montaje que contiene `path`: el punto de montaje más largo que es prefijo
+    /// de la ruta. A igual longitud gana el último, que tapa a los anteriores.
+    pub fn find_mount(&self, path: &Path) -> Option<MountEntry> {
+        let resolved = match self.resolve_paths {
+            true => resolve_path(path),
+            false => path.to_path_buf(),
+        };
+
+        self.mounts()
+            .into_iter()
+            .filter(|mount| resolved.starts_with(&mount.mount_point))
+            .fold(None, |best: Option<MountEntry>, mount| match best {
+                Some(best) if best.mount_point.components().count() > mount.mount_point.components().count() => Some(best),
+                _ => Some(mount),
+            })
+    }
+
+    /// Directorio en sysfs del disco completo que hay bajo un montaje
+    fn block_device_dir(&self, mount: &MountEntry) -> Option<PathBuf> {
+        // Btrfs y otros usan un dispositivo anónimo (0:n); el real es el origen
+        let dir = if mount.major == 0 && mount.source.starts_with("/dev/") {
+            self.sys_root.join("class/block").join(self.source_name(mount)?)
+        } else {
+            self.sys_root.join(format!("dev/block/{}:{}", mount.major, mount.minor))
+        };
+
+        let mut dir = std::fs::canonicalize(dir).ok()?;
+
+        // dm-crypt, LVM y RAID: bajar al primer dispositivo que los respalda
+        for _ in 0..MAX_STACK_DEPTH {
+            let slave = std::fs::read_dir(dir.join("slaves"))
+                .ok()
+                .and_then(|mut entries| entries.next())
+                .and_then(|entry| entry.ok())
+                .and_then(|entry| std::fs::canonicalize(entry.path()).ok());
+
+            match slave {
+                Some(slave) => dir = slave,
+                None => break,
+            }
+        }
+
+        // Una partición cuelga del disco que la contiene
+        if dir.join("partition").exists() {
+            dir.pop();
+        }
+
+        Some(dir)
+    }
+
+    /// `sys/devices`, de donde cuelgan los discos; por encima no hay nada del hardware
+    fn sysfs_devices(&self) -> PathBuf {
+        let devices = self.sys_root.join("devices");
+        std::fs::canonicalize(&devices).unwrap_or(devices)
     }
 
     #[cfg(target_os = "linux")]
-    async fn detect_linux_device_type(&self, path: &PathBuf) -> DeviceType {
-        // Implementar detección para Linux
-        DeviceType::Unknown
+    fn detect_linux_device_type(&self, mount: &MountEntry, disk: Option<&Path>) -> DeviceType {
+        if matches!(mount.filesystem.as_str(), "tmpfs" | "ramfs") {
+            return DeviceType::RAMDisk;
+        }
+
+        let Some(disk) = disk else {
+            return DeviceType::Unknown;
+        };
+        let name = disk.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+        let devices = self.sysfs_devices();
+        let below = disk.strip_prefix(&devices).unwrap_or(Path::new(""));
+        let in_path = |prefix: &str| {
+            below.components().any(|c| c.as_os_str().to_string_lossy().starts_with(prefix))
+        };
+
+        if name.starts_with("ram") || name.starts_with("zram") {
+            DeviceType::RAMDisk
+        } else if name.starts_with("nvme") {
+            DeviceType::NVMeSSD
+        } else if name.starts_with("mmcblk") || in_path("mmc_host") {
+            DeviceType::SDCard
+        } else if name.starts_with("sr") {
+            DeviceType::Optical
+        } else if in_path("usb") {
+            self.usb_device_type(disk)
+        } else {
+            match read_attr(&disk.join("queue/rotational")).as_deref() {
+                Some("1") => DeviceType::HDD,
+                Some("0") => DeviceType::SataSSD,
+                _ => DeviceType::Unknown,
+            }
+        }
+    }
+
+    /// Clase USB según la velocidad negociada (Mbit/s) del dispositivo USB más
+    /// cercano al disco: 1.5/12 es USB1, 480 USB2, 5000 o más USB3
+    fn usb_device_type(&self, disk: &Path) -> DeviceType {
+        let devices = self.sysfs_devices();
+        let speed = disk.ancestors()
+            .take_while(|dir| dir.starts_with(&devices))
+            .find_map(|dir| read_attr(&dir.join("speed")))
+            .and_then(|speed| speed.parse::<f64>().ok());
+
+        match speed {
+            Some(speed) if speed >= 5000.0 => DeviceType::USB3,
+            Some(speed) if speed >= 480.0 => DeviceType::USB2,
+            Some(_) => DeviceType::USB1,
+            None => DeviceType::USB2,
+        }
     }
 
+    /// Extraíble según sysfs; los discos USB cuentan aunque no lo declaren
     #[cfg(target_os = "linux")]
-    async fn is_removable_linux(&self, path: &PathBuf) -> bool {
-        false
+    fn is_removable_linux(&self, device_type: &DeviceType, disk: Option<&Path>) -> bool {
+        let declared = disk
+            .and_then(|disk| read_attr(&disk.join("removable")))
+            .map(|value| value == "1")
+            .unwrap_or(false);
+
+        declared || matches!(device_type, DeviceType::USB1 | DeviceType::USB2 | DeviceType::USB3)
     }
 
+    /// Solo lectura si lo está el montaje o el propio dispositivo
     #[cfg(target_os = "linux")]
-    async fn is_read_only_linux(&self, path: &PathBuf) -> bool {
-        false
+    fn is_read_only_linux(&self, mount: &MountEntry, disk: Option<&Path>) -> bool {
+        mount.read_only
+            || disk
+                .and_then(|disk| read_attr(&disk.join("ro")))
+                .map(|value| value == "1")
+                .unwrap_or(false)
     }
 
     fn estimate_speed(&self, device_type: &DeviceType) -> Option<f64> {
@@ -120,4 +566,124 @@ impl DeviceMonitor {
             _ => None,
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::test_support::ScratchDir;
+
+    fn write(path: &Path, content: &str) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
+    /// Disco en `sys/devices/<device>` con su enlace en `dev/block` y `class/block`.
+    /// Las particiones (`<disco>/<nombre>`) llevan el atributo `partition`.
+    fn block(sys: &Path, dev: &str, device: &str) {
+        let dir = sys.join("devices").join(device);
+        std::fs::create_dir_all(&dir).unwrap();
+        if dir.parent().and_then(|parent| parent.file_name()) != Some(std::ffi::OsStr::new("block")) {
+            write(&dir.join("partition"), "1");
+        }
+
+        let name = dir.file_name().unwrap();
+        for link in [sys.join("dev/block").join(dev), sys.join("class/block").join(name)] {
+            std::fs::create_dir_all(link.parent().unwrap()).unwrap();
+            std::os::unix::fs::symlink(&dir, link).unwrap();
+        }
+    }
+
+    fn mountinfo(mounts: &[(&str, &str, &str, &str)]) -> String {
+        mounts
+            .iter()
+            .enumerate()
+            .map(|(id, (dev, point, fs, source))| format!("{} 1 {} / {} rw,relatime - {} {} rw\n", id + 20, dev, point, fs, source))
+            .collect()
+    }
+
+    #[test]
+    fn mount_entries_are_parsed_and_unescaped() {
+        let line = r"36 25 8:17 / /media/mis\040fotos rw,nosuid shared:1 - vfat /dev/sdb1 ro,fmask=0022";
+        let mount = MountEntry::parse(line).unwrap();
+
+        assert_eq!((mount.major, mount.minor), (8, 17));
+        assert_eq!(mount.mount_point, Path::new("/media/mis fotos"));
+        assert_eq!(mount.filesystem, "vfat");
+        assert_eq!(mount.source, "/dev/sdb1");
+        assert!(mount.read_only && mount.is_block_device());
+        assert!(MountEntry::parse("36 25 8:17 / /media rw").is_none());
+
+        assert_eq!(unescape(r"a\040b\011c\134d"), "a b\tc\\d");
+        assert_eq!(unescape(r"fin\04"), r"fin\04");
+        assert_eq!(unescape(r"no\999"), r"no\999");
+    }
+
+    #[test]
+    fn find_mount_picks_the_longest_prefix() {
+        let root = ScratchDir::new("device-mounts");
+        write(&root.join("proc/self/mountinfo"), &mountinfo(&[
+            ("8:1", "/", "ext4", "/dev/sda1"),
+            ("8:2", "/mnt", "ext4", "/dev/sda2"),
+            ("8:17", "/mnt/datos", "ext4", "/dev/sdb1"),
+            ("8:33", "/mnt/datos", "ext4", "/dev/sdc1"),
+        ]));
+        let monitor = DeviceMonitor::new().with_roots(root.join("proc"), root.join("sys"));
+
+        let source = |path: &str| monitor.find_mount(Path::new(path)).map(|mount| mount.source);
+        // El último montaje sobre el mismo punto tapa al anterior
+        assert_eq!(source("/mnt/datos/fotos").as_deref(), Some("/dev/sdc1"));
+        // Por componentes: /mnt/datos2 no está dentro de /mnt/datos
+        assert_eq!(source("/mnt/datos2").as_deref(), Some("/dev/sda2"));
+        assert_eq!(source("/home").as_deref(), Some("/dev/sda1"));
+    }
+
+    #[tokio::test]
+    async fn disks_are_classified_from_sysfs() {
+        let root = ScratchDir::new("device-types");
+        // Un componente "usb" por encima de sys/devices no debe contar como USB
+        let sys = root.join("usb-tree/sys");
+        let dev = root.join("dev");
+
+        block(&sys, "8:1", "pci0000:00/ata1/host0/target0:0:0/0:0:0:0/block/sda/sda1");
+        write(&sys.join("devices/pci0000:00/ata1/host0/target0:0:0/0:0:0:0/block/sda/queue/rotational"), "1");
+        block(&sys, "8:2", "pci0000:00/ata2/host1/target1:0:0/1:0:0:0/block/sdd/sdd1");
+        write(&sys.join("devices/pci0000:00/ata2/host1/target1:0:0/1:0:0:0/block/sdd/queue/rotational"), "0");
+        block(&sys, "259:1", "pci0000:00/0000:00:1d.0/nvme/nvme0/nvme0n1/nvme0n1p1");
+        block(&sys, "8:17", "pci0000:00/0000:00:14.0/usb2/2-1/2-1:1.0/host6/target6:0:0/6:0:0:0/block/sdb/sdb1");
+        write(&sys.join("devices/pci0000:00/0000:00:14.0/usb2/2-1/speed"), "5000");
+        block(&sys, "179:1", "platform/soc/mmc_host/mmc0/mmc0:0001/block/mmcblk0/mmcblk0p1");
+        // Btrfs: dispositivo anónimo, el disco se busca por el origen
+        block(&sys, "8:33", "pci0000:00/ata3/host2/target2:0:0/2:0:0:0/block/sdc/sdc1");
+        write(&sys.join("devices/pci0000:00/ata3/host2/target2:0:0/2:0:0:0/block/sdc/queue/rotational"), "1");
+        write(&dev.join("sdc1"), "");
+
+        write(&root.join("proc/self/mountinfo"), &mountinfo(&[
+            ("8:1", "/mnt/hdd", "ext4", "/dev/sda1"),
+            ("8:2", "/mnt/ssd", "ext4", "/dev/sdd1"),
+            ("259:1", "/mnt/nvme", "ext4", "/dev/nvme0n1p1"),
+            ("8:17", "/media/usb", "vfat", "/dev/sdb1"),
+            ("179:1", "/media/sd", "exfat", "/dev/mmcblk0p1"),
+            ("0:45", "/mnt/btrfs", "btrfs", "/dev/sdc1"),
+            ("0:30", "/mnt/ram", "tmpfs", "tmpfs"),
+        ]));
+        let mut monitor = DeviceMonitor::new()
+            .with_roots(root.join("proc"), &sys)
+            .with_dev_root(&dev);
+
+        let expected = [
+            ("/mnt/hdd/a", "HDD"),
+            ("/mnt/ssd", "SataSSD"),
+            ("/mnt/nvme", "NVMeSSD"),
+            ("/media/usb", "USB3"),
+            ("/media/sd", "SDCard"),
+            ("/mnt/btrfs", "HDD"),
+            ("/mnt/ram", "RAMDisk"),
+        ];
+        for (path, device_type) in expected {
+            let info = monitor.detect_device(&PathBuf::from(path)).await;
+            assert_eq!(format!("{:?}", info.device_type), device_type, "{}", path);
+            assert_eq!(info.is_removable, device_type == "USB3", "{}", path);
+        }
+    }
+}
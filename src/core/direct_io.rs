@@ -0,0 +1,111 @@
+// src/core/direct_io.rs
+// Soporte para O_DIRECT (sin caché de páginas) y escrituras síncronas
+use std::alloc::{alloc_zeroed, dealloc, Layout};
+use std::fs::{File, OpenOptions};
+use std::os::unix::fs::{MetadataExt, OpenOptionsExt};
+use std::path::{Path, PathBuf};
+
+use nix::libc;
+
+use crate::core::copy_engine::SyncMode;
+
+/// Buffer alineado en memoria, como exige O_DIRECT
+pub struct AlignedBuffer {
+    ptr: *mut u8,
+    layout: Layout,
+}
+
+// El buffer es propiedad exclusiva de un hilo cada vez
+unsafe impl Send for AlignedBuffer {}
+
+impl AlignedBuffer {
+    /// `size` se redondea hacia arriba a un múltiplo de `alignment`
+    pub fn new(size: usize, alignment: usize) -> Self {
+        let alignment = alignment.max(512).next_power_of_two();
+        let size = size.max(alignment).div_ceil(alignment) * alignment;
+        let layout = Layout::from_size_align(size, alignment).expect("layout de buffer alineado");
+
+        let ptr = unsafe { alloc_zeroed(layout) };
+        if ptr.is_null() {
+            std::alloc::handle_alloc_error(layout);
+        }
+
+        Self { ptr, layout }
+    }
+
+    pub fn alignment(&self) -> usize {
+        self.layout.align()
+    }
+}
+
+impl std::ops::Deref for AlignedBuffer {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        unsafe { std::slice::from_raw_parts(self.ptr, self.layout.size()) }
+    }
+}
+
+impl std::ops::DerefMut for AlignedBuffer {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        unsafe { std::slice::from_raw_parts_mut(self.ptr, self.layout.size()) }
+    }
+}
+
+impl Drop for AlignedBuffer {
+    fn drop(&mut self) {
+        unsafe { dealloc(self.ptr, self.layout) }
+    }
+}
+
+/// Redondear hacia arriba a un múltiplo de `alignment`
+pub fn align_up(value: u64, alignment: u64) -> u64 {
+    value.div_ceil(alignment) * alignment
+}
+
+/// Flags de open() para el modo de sincronización elegido
+pub fn sync_flags(mode: SyncMode) -> i32 {
+    match mode {
+        SyncMode::None => 0,
+        SyncMode::Data => libc::O_DSYNC,
+        SyncMode::Full => libc::O_SYNC,
+    }
+}
+
+/// Tamaño de bloque lógico del disco donde está `file`, que es la alineación que
+/// exige O_DIRECT en él. `None` si no es un disco de bloques (tmpfs, red, FUSE...).
+pub fn logical_block_size(file: &File) -> Option<usize> {
+    let dev = file.metadata().ok()?.dev();
+    let device = PathBuf::from(format!(
+        "/sys/dev/block/{}:{}",
+        nix::sys::stat::major(dev),
+        nix::sys::stat::minor(dev)
+    ));
+
+    // Una partición no tiene queue/: el valor está en el disco que la contiene
+    [device.join("queue/logical_block_size"), device.join("../queue/logical_block_size")]
+        .iter()
+        .find_map(|path| std::fs::read_to_string(path).ok()?.trim().parse().ok())
+}
+
+/// Abrir origen y destino con O_DIRECT. Devuelve `None` si el sistema de
+/// archivos lo rechaza (tmpfs, algunos FUSE...) para seguir con E/S normal.
+pub fn open_direct(source: &Path, dest: &Path, sync: SyncMode) -> Option<(File, File)> {
+    let src = OpenOptions::new()
+        .read(true)
+        .custom_flags(libc::O_DIRECT)
+        .open(source);
+
+    let dst = OpenOptions::new()
+        .write(true)
+        .custom_flags(libc::O_DIRECT | sync_flags(sync))
+        .open(dest);
+
+    match (src, dst) {
+        (Ok(src), Ok(dst)) => Some((src, dst)),
+        (Err(e), _) | (_, Err(e)) => {
+            log::info!("O_DIRECT no disponible para {}: {}; usando E/S con caché", dest.display(), e);
+            None
+        }
+    }
+}
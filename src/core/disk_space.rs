@@ -0,0 +1,190 @@
+// src/core/disk_space.rs
+// Destino lleno a mitad de copia: cuánto falta, esperar a que se libere
+// espacio y qué hacer si el usuario no quiere esperar
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use tokio::sync::oneshot;
+
+use crate::core::control::TransferControl;
+use crate::core::device_queue::{ItemTransferStatus, TransferItem};
+use crate::core::error_recovery::{CopyError, IoOperation, IoResultExt};
+
+/// Cada cuánto se mira el espacio libre mientras se espera
+pub const SPACE_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Holgura sobre lo estimado: metadatos, bloques indirectos, bloques reservados
+pub const SPACE_MARGIN: u64 = 64 * 1024 * 1024;
+
+const MB: u64 = 1024 * 1024;
+
+/// Espacio libre para el usuario (f_bavail) en el sistema de archivos de `path`,
+/// aunque `path` aún no exista
+pub fn available_space(path: &Path) -> Result<u64, CopyError> {
+    let existing = path.ancestors().find(|candidate| candidate.exists()).unwrap_or(path);
+    let stat = nix::sys::statvfs::statvfs(existing).io_context(IoOperation::Metadata, existing)?;
+    Ok(stat.blocks_available() as u64 * stat.fragment_size() as u64)
+}
+
+/// Cuánto espacio hace falta para terminar y cuánto hay
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpaceShortage {
+    pub destination: PathBuf,
+    pub required: u64,  // Lo que queda por escribir, con holgura
+    pub available: u64,
+}
+
+impl SpaceShortage {
+    /// Estimar lo que falta para terminar: `remaining` bytes por copiar.
+    /// Si el disco se llenó aunque las cuentas dijeran que cabía, se pide al menos
+    /// la holgura por encima de lo libre, para no reintentar sin que cambie nada.
+    pub fn new(destination: &Path, remaining: u64, available: u64) -> Self {
+        Self {
+            destination: destination.to_path_buf(),
+            required: (remaining + SPACE_MARGIN).max(available + SPACE_MARGIN),
+            available,
+        }
+    }
+
+    /// Bytes que hay que liberar
+    pub fn missing(&self) -> u64 {
+        self.required.saturating_sub(self.available)
+    }
+}
+
+impl std::fmt::Display for SpaceShortage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Faltan {} MB en {}: hacen falta {} MB y hay {} MB libres",
+            self.missing().div_ceil(MB),
+            self.destination.display(),
+            self.required.div_ceil(MB),
+            self.available / MB
+        )
+    }
+}
+
+/// Cómo sigue una transferencia que se quedó sin espacio
+#[derive(Debug, Clone, PartialEq)]
+pub enum SpaceDecision {
+    /// Ya hay espacio: seguir desde el temporal que quedó a medias
+    Resume,
+    /// Saltar el archivo actual y los que no quepan en lo que quede libre
+    SkipLargeFiles,
+    /// Copiar lo que falta en otro destino; lo ya copiado se queda donde está
+    MoveTo(PathBuf),
+    Abort,
+}
+
+/// Aviso de destino lleno, del job al planificador y de este a la interfaz
+#[derive(Debug)]
+pub struct SpaceRequest {
+    pub transfer_name: String,
+    pub shortage: SpaceShortage,
+    pub control: std::sync::Arc<TransferControl>, // Para dejar de esperar si se cancela
+    pub response_sender: oneshot::Sender<SpaceDecision>,
+}
+
+/// Esperar hasta que haya `required` bytes libres en el destino.
+/// No usa `checkpoint`: quien espera suele tener su cola en pausa.
+pub async fn wait_for_space(destination: &Path, required: u64, control: &TransferControl) -> Result<(), CopyError> {
+    loop {
+        if control.is_cancelled() {
+            return Err(CopyError::Cancelled);
+        }
+        // Un error de statvfs (p. ej. el disco desmontado) no es motivo para dejar de esperar
+        match available_space(destination) {
+            Ok(available) if available >= required => return Ok(()),
+            Ok(_) => {}
+            Err(e) => log::debug!("Sin espacio libre conocido en {}: {}", destination.display(), e),
+        }
+        tokio::time::sleep(SPACE_POLL_INTERVAL).await;
+    }
+}
+
+/// Los elementos que aún no se han copiado, con los directorios que los contienen,
+/// para llevar lo que falta de una transferencia a otro destino
+pub fn unfinished_items(items: &[TransferItem]) -> Vec<TransferItem> {
+    items
+        .iter()
+        .filter_map(|item| {
+            let children = unfinished_items(&item.children);
+            // Lo saltado tampoco está en el destino, así que se lleva también
+            let finished = matches!(item.status, ItemTransferStatus::Completed);
+
+            (!finished || !children.is_empty()).then(|| TransferItem {
+                status: ItemTransferStatus::Pending,
+                children,
+                ..item.clone()
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::device_queue::ItemType;
+
+    fn item(name: &str, status: ItemTransferStatus, children: Vec<TransferItem>) -> TransferItem {
+        TransferItem {
+            source_path: PathBuf::from("/origen").join(name),
+            relative_path: PathBuf::from(name),
+            item_type: if children.is_empty() { ItemType::File } else { ItemType::Directory },
+            size: 1,
+            children,
+            status,
+            link_target: None,
+            hardlink_key: None,
+        }
+    }
+
+    #[test]
+    fn shortage_covers_what_is_left_plus_margin() {
+        let shortage = SpaceShortage::new(Path::new("/destino"), 500 * MB, 100 * MB);
+
+        assert_eq!(shortage.required, 500 * MB + SPACE_MARGIN);
+        assert_eq!(shortage.missing(), 400 * MB + SPACE_MARGIN);
+        assert!(shortage.to_string().starts_with("Faltan 464 MB en /destino"));
+    }
+
+    #[test]
+    fn shortage_asks_for_margin_when_estimate_said_it_fit() {
+        // El disco se llenó aunque quedaba más de lo que faltaba por copiar
+        let shortage = SpaceShortage::new(Path::new("/destino"), 10 * MB, 100 * MB);
+
+        assert_eq!(shortage.required, 100 * MB + SPACE_MARGIN);
+        assert_eq!(shortage.missing(), SPACE_MARGIN);
+    }
+
+    #[test]
+    fn available_space_uses_nearest_existing_ancestor() {
+        let missing = std::env::temp_dir().join("copymaster-no-existe/a/b");
+
+        assert!(available_space(&missing).unwrap() > 0);
+    }
+
+    #[test]
+    fn unfinished_items_keep_pending_files_and_their_parents() {
+        let tree = vec![
+            item("hecho", ItemTransferStatus::Completed, vec![
+                item("hecho/a", ItemTransferStatus::Completed, Vec::new()),
+            ]),
+            item("a-medias", ItemTransferStatus::Completed, vec![
+                item("a-medias/a", ItemTransferStatus::Completed, Vec::new()),
+                item("a-medias/b", ItemTransferStatus::Copying, Vec::new()),
+            ]),
+            item("suelto", ItemTransferStatus::Pending, Vec::new()),
+            item("saltado", ItemTransferStatus::Skipped("no cabe".to_string()), Vec::new()),
+        ];
+
+        let left = unfinished_items(&tree);
+
+        let names: Vec<_> = left.iter().map(|item| item.relative_path.clone()).collect();
+        assert_eq!(names, [PathBuf::from("a-medias"), PathBuf::from("suelto"), PathBuf::from("saltado")]);
+        assert_eq!(left[0].children.len(), 1);
+        assert_eq!(left[0].children[0].relative_path, PathBuf::from("a-medias/b"));
+        assert!(left.iter().all(|item| matches!(item.status, ItemTransferStatus::Pending)));
+    }
+}
@@ -19,37 +19,33 @@ pub enum DropAction {
     Cancel,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub enum Priority {
-    Low,
-    Normal,
-    High,
-    Critical,
-}
-
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TransferRequest {
     pub id: u64,
     pub source_items: Vec<DroppedItem>,
     pub destination: PathBuf,
     pub device_id: String,
+    #[serde(skip, default = "Instant::now")]
     pub created_at: Instant,
     pub options: TransferOptions,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TransferOptions {
-    pub priority: Priority,
+    pub priority: crate::core::device_queue::QueuePriority,
     pub verify_after_copy: bool,
     pub preserve_attributes: bool,
+    #[serde(default)]
+    pub bandwidth_limit_mbps: Option<f64>, // Límite propio de esta transferencia
 }
 
 impl Default for TransferOptions {
     fn default() -> Self {
         Self {
-            priority: Priority::Normal,
+            priority: crate::core::device_queue::QueuePriority::Normal,
             verify_after_copy: true,
             preserve_attributes: true,
+            bandwidth_limit_mbps: None,
         }
     }
 }
@@ -63,6 +59,11 @@ pub struct TransferResult {
     pub directories_created: usize,
     pub duration: std::time::Duration,
     pub errors: Vec<String>,
+    pub skipped: Vec<String>,
+    pub retries: Vec<crate::core::error_recovery::RetryAttempt>, // Cada reintento, aunque acabara bien
+    pub active_bytes: u64,                   // Escritos por el motor, sin huecos ni lo ya copiado
+    pub active_duration: std::time::Duration, // Tiempo copiando datos, sin diálogos ni esperas
+    pub interrupted: bool,                   // Hubo pausa, espera de espacio o errores que atender
 }
 
 impl TransferResult {
@@ -75,6 +76,11 @@ impl TransferResult {
             directories_created: 0,
             duration: std::time::Duration::from_secs(0),
             errors: Vec::new(),
+            skipped: Vec::new(),
+            retries: Vec::new(),
+            active_bytes: 0,
+            active_duration: std::time::Duration::from_secs(0),
+            interrupted: false,
         }
     }
 }
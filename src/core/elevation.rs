@@ -0,0 +1,290 @@
+// src/core/elevation.rs
+// Repetir como administrador las operaciones que fallaron por permisos, a través
+// de copymaster-helper lanzado con pkexec. Al ayudante se le pasa la lista exacta
+// de rutas que puede tocar; la aplicación sigue sin privilegios.
+use std::io::{BufRead, BufReader, Read, Write};
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+use crate::core::error_recovery::{CopyError, IoOperation, IoResultExt};
+use crate::core::helper_protocol::{HelperRequest, HelperResponse};
+
+/// Ruta del ayudante; la política de polkit la autoriza por esta ruta exacta
+pub const HELPER_PATH: &str = match option_env!("COPYMASTER_HELPER_PATH") {
+    Some(path) => path,
+    None => "/usr/local/libexec/copymaster-helper",
+};
+
+/// Tamaño de cada escritura enviada al ayudante
+const CHUNK_SIZE: usize = 1024 * 1024;
+
+/// Permisos del temporal mientras se escribe como root
+const PART_MODE: u32 = 0o600;
+
+/// Códigos de salida de pkexec
+const PKEXEC_DISMISSED: i32 = 126;
+const PKEXEC_NOT_AUTHORIZED: i32 = 127;
+
+/// Ruta tal como la verá el ayudante: absoluta y con el directorio padre resuelto.
+/// El último componente no se resuelve, para sustituir un enlace y no su destino.
+fn helper_path(path: &Path) -> PathBuf {
+    let absolute = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        std::env::current_dir().map(|dir| dir.join(path)).unwrap_or_else(|_| path.to_path_buf())
+    };
+
+    match (absolute.parent(), absolute.file_name()) {
+        (Some(parent), Some(name)) => crate::core::device_detector::resolve_path(parent).join(name),
+        _ => absolute,
+    }
+}
+
+/// Una sesión con el ayudante; se cierra al soltarla
+pub struct ElevatedSession {
+    child: Child,
+    input: Option<ChildStdin>,
+    output: BufReader<ChildStdout>,
+}
+
+impl ElevatedSession {
+    /// Lanzar el ayudante con pkexec autorizando solo `paths`.
+    /// Bloquea mientras el usuario se autentica.
+    pub fn start(paths: &[PathBuf]) -> Result<Self, CopyError> {
+        let mut child = Command::new("pkexec")
+            .arg(HELPER_PATH)
+            .args(paths)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|e| CopyError::ElevationFailed(format!("no se pudo ejecutar pkexec: {}", e)))?;
+
+        let input = child.stdin.take();
+        let output = child.stdout.take().map(BufReader::new);
+        let Some(output) = output else {
+            let _ = child.kill();
+            return Err(CopyError::ElevationFailed("sin salida del ayudante".to_string()));
+        };
+
+        let mut session = Self { child, input, output };
+        // El ayudante responde Ok en cuanto pkexec lo ha autorizado
+        session.receive(IoOperation::Open, Path::new(HELPER_PATH))?;
+        Ok(session)
+    }
+
+    /// Esperar la respuesta a la última petición
+    fn receive(&mut self, operation: IoOperation, path: &Path) -> Result<(), CopyError> {
+        let mut line = String::new();
+        let read = self.output.read_line(&mut line).map_err(|e| CopyError::ElevationFailed(e.to_string()))?;
+
+        if read == 0 {
+            return Err(self.exit_error());
+        }
+
+        match serde_json::from_str(&line) {
+            Ok(HelperResponse::Ok) => Ok(()),
+            Ok(HelperResponse::Failed { os_error, message }) => {
+                let error = match os_error {
+                    Some(code) => std::io::Error::from_raw_os_error(code),
+                    None => std::io::Error::other(message),
+                };
+                Err(CopyError::from_io(error, operation, path))
+            }
+            Ok(HelperResponse::Refused(reason)) => Err(CopyError::ElevationFailed(reason)),
+            Err(e) => Err(CopyError::ElevationFailed(format!("respuesta no válida del ayudante: {}", e))),
+        }
+    }
+
+    /// Por qué terminó el ayudante antes de tiempo
+    fn exit_error(&mut self) -> CopyError {
+        self.input = None;
+        let reason = match self.child.wait().map(|status| status.code()) {
+            Ok(Some(PKEXEC_DISMISSED)) => "autenticación cancelada".to_string(),
+            Ok(Some(PKEXEC_NOT_AUTHORIZED)) => "no autorizado".to_string(),
+            Ok(Some(code)) => format!("el ayudante terminó con código {}", code),
+            Ok(None) => "el ayudante terminó por una señal".to_string(),
+            Err(e) => e.to_string(),
+        };
+        CopyError::ElevationFailed(reason)
+    }
+
+    fn send(&mut self, request: &HelperRequest, data: &[u8]) -> Result<(), CopyError> {
+        let Some(input) = self.input.as_mut() else {
+            return Err(CopyError::ElevationFailed("la sesión está cerrada".to_string()));
+        };
+
+        let mut line = serde_json::to_vec(request).map_err(|e| CopyError::ElevationFailed(e.to_string()))?;
+        line.push(b'\n');
+
+        let sent = input
+            .write_all(&line)
+            .and_then(|_| input.write_all(data))
+            .and_then(|_| input.flush());
+        // Si el ayudante murió, su código de salida explica más que EPIPE
+        if sent.is_err() {
+            return Err(self.exit_error());
+        }
+        Ok(())
+    }
+
+    fn request(&mut self, request: HelperRequest, operation: IoOperation, path: &Path) -> Result<(), CopyError> {
+        self.send(&request, &[])?;
+        self.receive(operation, path)
+    }
+
+    pub fn create_dir(&mut self, path: &Path, mode: u32) -> Result<(), CopyError> {
+        let request = HelperRequest::CreateDir { path: path.to_path_buf(), mode };
+        self.request(request, IoOperation::CreateDir, path)
+    }
+
+    pub fn create_file(&mut self, path: &Path, mode: u32) -> Result<(), CopyError> {
+        let request = HelperRequest::CreateFile { path: path.to_path_buf(), mode };
+        self.request(request, IoOperation::Create, path)
+    }
+
+    pub fn write(&mut self, path: &Path, offset: u64, data: &[u8]) -> Result<(), CopyError> {
+        let request = HelperRequest::Write {
+            path: path.to_path_buf(),
+            offset,
+            len: data.len() as u64,
+        };
+        self.send(&request, data)?;
+        self.receive(IoOperation::Write, path)
+    }
+
+    pub fn set_permissions(&mut self, path: &Path, mode: u32) -> Result<(), CopyError> {
+        let request = HelperRequest::SetPermissions { path: path.to_path_buf(), mode };
+        self.request(request, IoOperation::SetPermissions, path)
+    }
+
+    pub fn set_owner(&mut self, path: &Path, uid: u32, gid: u32) -> Result<(), CopyError> {
+        let request = HelperRequest::SetOwner { path: path.to_path_buf(), uid, gid };
+        self.request(request, IoOperation::SetOwner, path)
+    }
+
+    pub fn rename(&mut self, from: &Path, to: &Path) -> Result<(), CopyError> {
+        let request = HelperRequest::Rename { from: from.to_path_buf(), to: to.to_path_buf() };
+        self.request(request, IoOperation::Rename, to)
+    }
+
+    /// Cerrar la sesión de forma ordenada
+    pub fn finish(mut self) -> Result<(), CopyError> {
+        self.request(HelperRequest::Finish, IoOperation::Sync, Path::new(HELPER_PATH))
+    }
+}
+
+impl Drop for ElevatedSession {
+    fn drop(&mut self) {
+        // Sin stdin el ayudante sale y suelta lo que tenga abierto
+        self.input = None;
+        let _ = self.child.wait();
+    }
+}
+
+/// Copiar cada `(origen, destino)` de `files` como administrador, todos en la
+/// misma sesión y con una sola autenticación. Cada archivo se escribe como en la
+/// copia normal: temporal oculto propiedad del usuario, permisos del origen y
+/// rename al final. `on_chunk` recibe el índice del archivo y los bytes de cada
+/// escritura, y puede cancelar devolviendo un error.
+/// Devuelve el resultado de cada archivo. Bloquea; llamar desde `spawn_blocking`.
+pub fn copy_files(
+    files: &[(PathBuf, PathBuf)],
+    mut on_chunk: impl FnMut(usize, u64) -> Result<(), CopyError>,
+) -> Vec<Result<u64, CopyError>> {
+    let targets: Vec<(PathBuf, PathBuf)> = files
+        .iter()
+        .map(|(_, dest)| {
+            let dest = helper_path(dest);
+            (crate::core::atomic_write::part_path(&dest), dest)
+        })
+        .collect();
+    let granted: Vec<PathBuf> = targets.iter().flat_map(|(part, dest)| [part.clone(), dest.clone()]).collect();
+
+    let mut session = match ElevatedSession::start(&granted) {
+        Ok(session) => session,
+        Err(e) => return files.iter().map(|_| Err(e.clone())).collect(),
+    };
+
+    let mut results = Vec::with_capacity(files.len());
+    // Sin ayudante o tras cancelar, el resto ya no se intenta
+    let mut stopped: Option<CopyError> = None;
+    for (index, ((source, _), (part, dest))) in files.iter().zip(&targets).enumerate() {
+        let result = match &stopped {
+            Some(error) => Err(error.clone()),
+            None => copy_one(&mut session, source, part, dest, |bytes| on_chunk(index, bytes)),
+        };
+        if let Err(e) = &result {
+            if matches!(e, CopyError::Cancelled) || session.input.is_none() {
+                stopped.get_or_insert_with(|| e.clone());
+            }
+        }
+        results.push(result);
+    }
+
+    if stopped.is_none() {
+        if let Err(e) = session.finish() {
+            log::warn!("El ayudante no cerró bien la sesión: {}", e);
+        }
+    }
+    results
+}
+
+/// Copiar un archivo dentro de una sesión ya autorizada para `part` y `dest`
+fn copy_one(
+    session: &mut ElevatedSession,
+    source: &Path,
+    part: &Path,
+    dest: &Path,
+    mut on_chunk: impl FnMut(u64) -> Result<(), CopyError>,
+) -> Result<u64, CopyError> {
+    let mut input = std::fs::File::open(source).io_context(IoOperation::Open, source)?;
+    let metadata = input.metadata().io_context(IoOperation::Metadata, source)?;
+    let (uid, gid) = (nix::unistd::getuid().as_raw(), nix::unistd::getgid().as_raw());
+
+    session.create_file(part, PART_MODE)?;
+
+    let mut buffer = vec![0u8; CHUNK_SIZE];
+    let mut offset = 0;
+    loop {
+        let n = input.read(&mut buffer).io_context(IoOperation::Read, source)?;
+        if n == 0 {
+            break;
+        }
+        session.write(part, offset, &buffer[..n])?;
+        offset += n as u64;
+        on_chunk(n as u64)?;
+    }
+
+    // Primero el propietario: chown quita los bits especiales que hubiera
+    session.set_owner(part, uid, gid)?;
+    session.set_permissions(part, metadata.mode() & 0o777)?;
+    session.rename(part, dest)?;
+
+    Ok(offset)
+}
+
+/// Crear `dir` y los directorios que le falten como administrador, dejándolos
+/// a nombre del usuario para que el resto de la copia no necesite privilegios
+pub fn create_dir_all(dir: &Path, mode: u32) -> Result<(), CopyError> {
+    let dir = helper_path(dir);
+    let missing: Vec<PathBuf> = dir
+        .ancestors()
+        .take_while(|ancestor| std::fs::symlink_metadata(ancestor).is_err())
+        .map(Path::to_path_buf)
+        .collect();
+
+    if missing.is_empty() {
+        return Ok(());
+    }
+
+    let (uid, gid) = (nix::unistd::getuid().as_raw(), nix::unistd::getgid().as_raw());
+    let mut session = ElevatedSession::start(&missing)?;
+
+    for path in missing.iter().rev() {
+        session.create_dir(path, mode)?;
+        session.set_owner(path, uid, gid)?;
+    }
+
+    session.finish()
+}
@@ -1,22 +1,114 @@
 // src/core/error_recovery.rs
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use nix::errno::Errno;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+use crate::core::control::TransferControl;
+
+/// Cada cuánto se mira si hay que cancelar durante la espera entre intentos
+const BACKOFF_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Operación de E/S que falló
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IoOperation {
+    Open,
+    Create,
+    Read,
+    Write,
+    Sync,
+    Metadata,
+    ReadDir,
+    CreateDir,
+    Rename,
+    Remove,
+    Truncate,
+    Copy,
+    SetPermissions,
+    SetOwner,
+}
+
+impl std::fmt::Display for IoOperation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            IoOperation::Open => "open",
+            IoOperation::Create => "create",
+            IoOperation::Read => "read",
+            IoOperation::Write => "write",
+            IoOperation::Sync => "sync",
+            IoOperation::Metadata => "stat",
+            IoOperation::ReadDir => "read directory",
+            IoOperation::CreateDir => "create directory",
+            IoOperation::Rename => "rename",
+            IoOperation::Remove => "remove",
+            IoOperation::Truncate => "truncate",
+            IoOperation::Copy => "copy",
+            IoOperation::SetPermissions => "chmod",
+            IoOperation::SetOwner => "chown",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Dónde y cómo falló una operación de E/S, con el errno original
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IoContext {
+    pub operation: IoOperation,
+    pub path: PathBuf,
+    pub os_error: Option<i32>,
+    pub message: String,
+}
+
+impl std::fmt::Display for IoContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}: {}", self.operation, self.path.display(), self.message)
+    }
+}
+
 #[derive(Debug, Clone, Error)]
 pub enum CopyError {
     #[error("IO error: {0}")]
     Io(String),
     
-    #[error("Permission denied")]
-    PermissionDenied,
+    /// Error del sistema sin categoría propia
+    #[error("IO error: {0}")]
+    Os(IoContext),
+    
+    #[error("Permission denied: {0}")]
+    PermissionDenied(IoContext),
+    
+    #[error("Disk full: {0}")]
+    DiskFull(IoContext),
     
-    #[error("Disk full")]
-    DiskFull,
+    #[error("File locked: {0}")]
+    FileLocked(IoContext),
     
-    #[error("File locked")]
-    FileLocked,
+    #[error("Network error: {0}")]
+    NetworkError(IoContext),
     
-    #[error("Network error")]
-    NetworkError,
+    #[error("Not found: {0}")]
+    NotFound(IoContext),
+    
+    #[error("Read-only file system: {0}")]
+    ReadOnlyFilesystem(IoContext),
+    
+    /// EIO: fallo del soporte o del cable, no del programa
+    #[error("Device error: {0}")]
+    DeviceError(IoContext),
+    
+    #[error("Name too long: {0}")]
+    NameTooLong(IoContext),
+    
+    /// El sistema de archivos de destino no admite el nombre (EILSEQ)
+    #[error("Invalid file name: {0}")]
+    InvalidName(IoContext),
+    
+    #[error("File too large: {0}")]
+    FileTooLarge(IoContext),
     
     #[error("Hash mismatch")]
     HashMismatch,
@@ -30,26 +122,139 @@ pub enum CopyError {
     #[error("Invalid path")]
     InvalidPath,
     
-    #[error("Cross device link")]
-    CrossDeviceLink,
+    #[error("Cross device link: {0}")]
+    CrossDeviceLink(IoContext),
+    
+    #[error("Special file: {0}")]
+    SpecialFile(String),
+    
+    #[error("Pre-flight check failed: {0}")]
+    PreflightFailed(String),
+    
+    /// pkexec cancelado o denegado, o el ayudante rechazó la operación
+    #[error("Elevation failed: {0}")]
+    ElevationFailed(String),
 }
 
 impl CopyError {
+    /// Clasificar un error de E/S por su errno, conservando operación, ruta y código
+    pub fn from_io(error: std::io::Error, operation: IoOperation, path: &Path) -> Self {
+        let os_error = error.raw_os_error();
+        let context = IoContext {
+            operation,
+            path: path.to_path_buf(),
+            os_error,
+            message: error.to_string(),
+        };
+        
+        let Some(errno) = os_error.map(Errno::from_i32) else {
+            return CopyError::Os(context);
+        };
+        
+        match errno {
+            Errno::ENOSPC | Errno::EDQUOT => CopyError::DiskFull(context),
+            Errno::EACCES | Errno::EPERM => CopyError::PermissionDenied(context),
+            Errno::ETXTBSY | Errno::EBUSY | Errno::EAGAIN => CopyError::FileLocked(context),
+            Errno::EXDEV => CopyError::CrossDeviceLink(context),
+            Errno::EIO | Errno::ENXIO | Errno::ENODEV | Errno::EMEDIUMTYPE | Errno::ENOMEDIUM => CopyError::DeviceError(context),
+            Errno::ENAMETOOLONG => CopyError::NameTooLong(context),
+            Errno::EILSEQ => CopyError::InvalidName(context),
+            Errno::ENOENT => CopyError::NotFound(context),
+            Errno::EROFS => CopyError::ReadOnlyFilesystem(context),
+            Errno::EFBIG => CopyError::FileTooLarge(context),
+            Errno::ESTALE
+            | Errno::ETIMEDOUT
+            | Errno::ENETDOWN
+            | Errno::ENETUNREACH
+            | Errno::ENETRESET
+            | Errno::EHOSTDOWN
+            | Errno::EHOSTUNREACH
+            | Errno::ECONNABORTED
+            | Errno::ECONNRESET
+            | Errno::ECONNREFUSED
+            | Errno::ENOTCONN
+            | Errno::EREMOTEIO => CopyError::NetworkError(context),
+            _ => CopyError::Os(context),
+        }
+    }
+    
+    /// Contexto de E/S, si el error viene de una operación concreta
+    pub fn io_context(&self) -> Option<&IoContext> {
+        match self {
+            CopyError::Os(context)
+            | CopyError::PermissionDenied(context)
+            | CopyError::DiskFull(context)
+            | CopyError::FileLocked(context)
+            | CopyError::NetworkError(context)
+            | CopyError::NotFound(context)
+            | CopyError::ReadOnlyFilesystem(context)
+            | CopyError::DeviceError(context)
+            | CopyError::NameTooLong(context)
+            | CopyError::InvalidName(context)
+            | CopyError::FileTooLarge(context)
+            | CopyError::CrossDeviceLink(context) => Some(context),
+            _ => None,
+        }
+    }
+    
+    /// errno original, si lo hay
+    pub fn raw_os_error(&self) -> Option<i32> {
+        self.io_context().and_then(|context| context.os_error)
+    }
+    
+    /// Familia del error a efectos de reintento
+    pub fn class(&self) -> ErrorClass {
+        match self {
+            CopyError::FileLocked(_) => ErrorClass::Locked,
+            CopyError::NetworkError(_) => ErrorClass::Network,
+            CopyError::DeviceError(_) => ErrorClass::Device,
+            CopyError::DiskFull(_) => ErrorClass::DiskFull,
+            CopyError::HashMismatch => ErrorClass::Integrity,
+            _ => ErrorClass::Other,
+        }
+    }
+    
     pub fn can_retry(&self) -> bool {
-        matches!(self, CopyError::FileLocked | CopyError::NetworkError | CopyError::HashMismatch)
+        matches!(
+            self,
+            CopyError::FileLocked(_) | CopyError::NetworkError(_) | CopyError::DeviceError(_) | CopyError::HashMismatch
+        )
     }
     
     pub fn suggested_action(&self) -> RecoveryAction {
         match self {
-            CopyError::DiskFull => RecoveryAction::FreeSpace,
-            CopyError::PermissionDenied => RecoveryAction::RequestElevation,
-            CopyError::FileLocked => RecoveryAction::UnlockOrSkip,
-            CopyError::CrossDeviceLink => RecoveryAction::UseCopy,
-            _ => RecoveryAction::RetryOrSkip,
+            CopyError::DiskFull(_) => RecoveryAction::FreeSpace,
+            CopyError::PermissionDenied(_) => RecoveryAction::RequestElevation,
+            CopyError::FileLocked(_) => RecoveryAction::UnlockOrSkip,
+            CopyError::CrossDeviceLink(_) => RecoveryAction::UseCopy,
+            // Reintentar no cambia el nombre ni el tamaño ni el origen que falta
+            CopyError::NotFound(_)
+            | CopyError::NameTooLong(_)
+            | CopyError::InvalidName(_)
+            | CopyError::FileTooLarge(_)
+            | CopyError::ReadOnlyFilesystem(_) => RecoveryAction::Skip,
+            _ => RecoveryAction::Retry,
         }
     }
 }
 
+/// Convertir errores de E/S en `CopyError` indicando qué se hacía y sobre qué ruta
+pub trait IoResultExt<T> {
+    fn io_context(self, operation: IoOperation, path: &Path) -> Result<T, CopyError>;
+}
+
+impl<T> IoResultExt<T> for std::io::Result<T> {
+    fn io_context(self, operation: IoOperation, path: &Path) -> Result<T, CopyError> {
+        self.map_err(|e| CopyError::from_io(e, operation, path))
+    }
+}
+
+impl<T> IoResultExt<T> for nix::Result<T> {
+    fn io_context(self, operation: IoOperation, path: &Path) -> Result<T, CopyError> {
+        self.map_err(|errno| CopyError::from_io(errno.into(), operation, path))
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum RecoveryAction {
     Retry,
@@ -60,60 +265,320 @@ pub enum RecoveryAction {
     RequestElevation,
     UnlockOrSkip,
     UseCopy,
+    Abort,
+}
+
+/// Familias de errores con política de reintento propia
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ErrorClass {
+    Locked,    // ETXTBSY, EBUSY: otro proceso lo tiene abierto
+    Network,   // Montajes de red caídos o lentos
+    Device,    // EIO: cable flojo, sector dudoso
+    DiskFull,  // Solo tiene sentido tras liberar espacio
+    Integrity, // Hash distinto al verificar; se vuelve a copiar desde cero
+    Other,     // Permisos, nombres, rutas: repetir no cambia nada
+}
+
+/// Cuántas veces reintentar y cuánto esperar entre intentos
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub initial_delay_ms: u64,
+    pub max_delay_ms: u64,
+    #[serde(default = "default_backoff_factor")]
+    pub backoff_factor: f64,
+    #[serde(default = "default_jitter")]
+    pub jitter: f64, // Fracción aleatoria (±) sobre cada espera, para no reintentar a la vez
+}
+
+fn default_backoff_factor() -> f64 {
+    2.0
+}
+
+fn default_jitter() -> f64 {
+    0.2
+}
+
+impl RetryPolicy {
+    pub fn new(max_retries: u32, initial_delay: Duration, max_delay: Duration) -> Self {
+        Self {
+            max_retries,
+            initial_delay_ms: initial_delay.as_millis() as u64,
+            max_delay_ms: max_delay.as_millis() as u64,
+            backoff_factor: default_backoff_factor(),
+            jitter: default_jitter(),
+        }
+    }
+    
+    pub fn never() -> Self {
+        Self::new(0, Duration::ZERO, Duration::ZERO)
+    }
+    
+    /// Espera antes del reintento número `attempt` (desde 0): crece de forma
+    /// exponencial hasta `max_delay_ms` y se desplaza al azar según `jitter`
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let base = self.initial_delay_ms as f64 * self.backoff_factor.max(1.0).powi(attempt.min(32) as i32);
+        let capped = base.min(self.max_delay_ms.max(self.initial_delay_ms) as f64);
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        let factor = if jitter > 0.0 { 1.0 + rand::thread_rng().gen_range(-jitter..=jitter) } else { 1.0 };
+        Duration::from_millis((capped * factor) as u64)
+    }
+}
+
+/// Política de reintento de cada familia de errores
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetryPolicies {
+    pub locked: RetryPolicy,
+    pub network: RetryPolicy,
+    pub device: RetryPolicy,
+    pub disk_full: RetryPolicy,
+    pub integrity: RetryPolicy,
+    pub other: RetryPolicy,
+}
+
+impl Default for RetryPolicies {
+    fn default() -> Self {
+        Self {
+            locked: RetryPolicy::new(5, Duration::from_millis(500), Duration::from_secs(10)),
+            network: RetryPolicy::new(5, Duration::from_secs(1), Duration::from_secs(30)),
+            device: RetryPolicy::new(3, Duration::from_secs(2), Duration::from_secs(30)),
+            disk_full: RetryPolicy::new(1, Duration::ZERO, Duration::ZERO),
+            integrity: RetryPolicy::new(3, Duration::ZERO, Duration::ZERO),
+            other: RetryPolicy::never(),
+        }
+    }
+}
+
+impl RetryPolicies {
+    pub fn for_class(&self, class: ErrorClass) -> &RetryPolicy {
+        match class {
+            ErrorClass::Locked => &self.locked,
+            ErrorClass::Network => &self.network,
+            ErrorClass::Device => &self.device,
+            ErrorClass::DiskFull => &self.disk_full,
+            ErrorClass::Integrity => &self.integrity,
+            ErrorClass::Other => &self.other,
+        }
+    }
+}
+
+/// Un intento fallido que se volvió a probar
+#[derive(Debug, Clone)]
+pub struct RetryAttempt {
+    pub path: PathBuf,
+    pub attempt: u32, // 1 para el primer reintento
+    pub class: ErrorClass,
+    pub error: String,
+    pub resume_offset: u64, // Byte desde el que continúa el reintento
 }
 
 pub struct ErrorRecovery {
-    max_retries: usize,
-    retry_delay: std::time::Duration,
+    policies: RetryPolicies,
     auto_recover: bool,
+    temp_roots: Vec<std::path::PathBuf>, // Destinos donde buscar temporales abandonados
+    control: Option<Arc<TransferControl>>, // Para no esperar entre intentos si se cancela
 }
 
 impl ErrorRecovery {
+    /// La misma política para todos los errores transitorios
     pub fn new(max_retries: usize, retry_delay: std::time::Duration, auto_recover: bool) -> Self {
+        let uniform = RetryPolicy::new(max_retries as u32, retry_delay, retry_delay * 8);
+        let policies = RetryPolicies {
+            locked: uniform.clone(),
+            network: uniform.clone(),
+            device: uniform.clone(),
+            integrity: RetryPolicy { initial_delay_ms: 0, ..uniform },
+            ..RetryPolicies::default()
+        };
+        Self::from_policies(policies, auto_recover)
+    }
+    
+    pub fn from_policies(policies: RetryPolicies, auto_recover: bool) -> Self {
         Self {
-            max_retries,
-            retry_delay,
+            policies,
             auto_recover,
+            temp_roots: Vec::new(),
+            control: None,
         }
     }
     
+    /// Atender cancelaciones de este control durante las esperas
+    pub fn with_control(mut self, control: Arc<TransferControl>) -> Self {
+        self.control = Some(control);
+        self
+    }
+    
+    pub fn policies(&self) -> &RetryPolicies {
+        &self.policies
+    }
+    
+    /// Añadir un destino a revisar al limpiar temporales
+    pub fn add_temp_root(&mut self, root: std::path::PathBuf) {
+        if !self.temp_roots.contains(&root) {
+            self.temp_roots.push(root);
+        }
+    }
+    
+    /// Decidir si se reintenta tras el fallo número `attempt` (desde 0) sobre `source`.
+    /// Si se reintenta, primero intenta arreglar la causa y espera lo que marque la
+    /// política; si no, devuelve el propio error.
     pub async fn handle_error(
         &self,
         error: &CopyError,
-        transfer: &crate::core::device_queue::TransferItem,
+        source: &Path,
+        attempt: u32,
     ) -> Result<RecoveryAction, CopyError> {
-        if !error.can_retry() {
+        let policy = self.policies.for_class(error.class());
+        if matches!(error, CopyError::Cancelled | CopyError::Paused) || attempt >= policy.max_retries {
             return Err(error.clone());
         }
         
         // Intentar recuperación automática para ciertos errores
         match error {
-            CopyError::FileLocked => {
-                if self.auto_recover {
-                    self.unlock_file(&transfer.source_path).await?;
-                    return Ok(RecoveryAction::Retry);
-                }
+            CopyError::FileLocked(_) if self.auto_recover => {
+                self.unlock_file(source).await?;
             }
-            CopyError::DiskFull => {
-                if self.auto_recover {
-                    self.clean_temp_files().await?;
-                    return Ok(RecoveryAction::Retry);
+            CopyError::DiskFull(_) => {
+                let freed = if self.auto_recover { self.clean_temp_files().await? } else { 0 };
+                // Sin liberar nada el reintento volvería a llenar el disco
+                if freed == 0 {
+                    return Err(error.clone());
                 }
             }
             _ => {}
         }
         
+        self.backoff(policy.delay(attempt)).await?;
         Ok(RecoveryAction::Retry)
     }
     
-    async fn unlock_file(&self, path: &std::path::PathBuf) -> Result<(), CopyError> {
+    /// Esperar entre intentos, saliendo antes si se cancela
+    async fn backoff(&self, delay: Duration) -> Result<(), CopyError> {
+        let deadline = tokio::time::Instant::now() + delay;
+        
+        loop {
+            if self.control.as_ref().map(|control| control.is_cancelled()).unwrap_or(false) {
+                return Err(CopyError::Cancelled);
+            }
+            let now = tokio::time::Instant::now();
+            if now >= deadline {
+                return Ok(());
+            }
+            tokio::time::sleep((deadline - now).min(BACKOFF_POLL_INTERVAL)).await;
+        }
+    }
+    
+    async fn unlock_file(&self, path: &Path) -> Result<(), CopyError> {
         // Implementar lógica de desbloqueo
         // Por ahora, solo retornamos Ok
         Ok(())
     }
     
-    async fn clean_temp_files(&self) -> Result<(), CopyError> {
-        // Implementar limpieza de archivos temporales
-        Ok(())
+    /// Borrar los `.nombre.copymaster-part` que dejaron copias interrumpidas.
+    /// Se conservan los de transferencias con diario, que aún se pueden reanudar,
+    /// y los que están escribiendo otras copias de este proceso, tengan diario o no.
+    /// Devuelve los bytes liberados.
+    pub async fn clean_temp_files(&self) -> Result<u64, CopyError> {
+        let roots = self.temp_roots.clone();
+        
+        let freed = tokio::task::spawn_blocking(move || {
+            let keep: std::collections::HashSet<std::path::PathBuf> =
+                crate::core::journal::TransferJournal::journal_dir()
+                    .map(|dir| crate::core::journal::TransferJournal::interrupted(&dir))
+                    .unwrap_or_default()
+                    .iter()
+                    .flat_map(|record| record.part_files())
+                    .collect();
+            
+            roots
+                .iter()
+                .map(|root| crate::core::atomic_write::remove_stale_parts(root, &keep))
+                .sum::<u64>()
+        })
+        .await
+        .map_err(|e| CopyError::Io(e.to_string()))?;
+        
+        if freed > 0 {
+            log::info!("Temporales abandonados: {} bytes liberados", freed);
+        }
+        Ok(freed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn classify(errno: Errno) -> CopyError {
+        CopyError::from_io(std::io::Error::from_raw_os_error(errno as i32), IoOperation::Write, Path::new("/destino/a"))
+    }
+
+    #[test]
+    fn errno_maps_to_its_variant() {
+        assert!(matches!(classify(Errno::ENOSPC), CopyError::DiskFull(_)));
+        assert!(matches!(classify(Errno::EDQUOT), CopyError::DiskFull(_)));
+        assert!(matches!(classify(Errno::EACCES), CopyError::PermissionDenied(_)));
+        assert!(matches!(classify(Errno::ETXTBSY), CopyError::FileLocked(_)));
+        assert!(matches!(classify(Errno::EXDEV), CopyError::CrossDeviceLink(_)));
+        assert!(matches!(classify(Errno::EIO), CopyError::DeviceError(_)));
+        assert!(matches!(classify(Errno::ENAMETOOLONG), CopyError::NameTooLong(_)));
+        assert!(matches!(classify(Errno::EILSEQ), CopyError::InvalidName(_)));
+        assert!(matches!(classify(Errno::ENOENT), CopyError::NotFound(_)));
+        assert!(matches!(classify(Errno::EROFS), CopyError::ReadOnlyFilesystem(_)));
+        assert!(matches!(classify(Errno::EFBIG), CopyError::FileTooLarge(_)));
+        assert!(matches!(classify(Errno::ESTALE), CopyError::NetworkError(_)));
+        assert!(matches!(classify(Errno::EMLINK), CopyError::Os(_)));
+    }
+
+    #[test]
+    fn context_keeps_operation_path_and_errno() {
+        let error = classify(Errno::ENOSPC);
+        let context = error.io_context().unwrap();
+
+        assert_eq!(context.operation, IoOperation::Write);
+        assert_eq!(context.path, Path::new("/destino/a"));
+        assert_eq!(error.raw_os_error(), Some(Errno::ENOSPC as i32));
+        assert_eq!(error.class(), ErrorClass::DiskFull);
+    }
+
+    #[test]
+    fn errors_without_errno_stay_generic() {
+        let error = CopyError::from_io(std::io::Error::other("roto"), IoOperation::Read, Path::new("/a"));
+        assert!(matches!(error, CopyError::Os(_)));
+        assert_eq!(error.raw_os_error(), None);
+
+        let error = Err::<(), _>(Errno::EBUSY).io_context(IoOperation::Rename, Path::new("/a")).unwrap_err();
+        assert!(matches!(error, CopyError::FileLocked(_)));
+    }
+
+    #[test]
+    fn suggestions_follow_the_class() {
+        assert!(matches!(classify(Errno::EACCES).suggested_action(), RecoveryAction::RequestElevation));
+        assert!(matches!(classify(Errno::ENOSPC).suggested_action(), RecoveryAction::FreeSpace));
+        assert!(matches!(classify(Errno::ENOENT).suggested_action(), RecoveryAction::Skip));
+        assert!(matches!(classify(Errno::EIO).suggested_action(), RecoveryAction::Retry));
+        assert!(classify(Errno::EIO).can_retry());
+        assert!(!classify(Errno::EACCES).can_retry());
+    }
+
+    #[test]
+    fn delay_grows_until_the_cap() {
+        let mut policy = RetryPolicy::new(5, Duration::from_millis(100), Duration::from_millis(1000));
+        policy.jitter = 0.0;
+
+        let delays: Vec<_> = (0..6).map(|attempt| policy.delay(attempt).as_millis()).collect();
+        assert_eq!(delays, [100, 200, 400, 800, 1000, 1000]);
+    }
+
+    #[tokio::test]
+    async fn disk_full_is_not_retried_without_freeing_space() {
+        let recovery = ErrorRecovery::from_policies(RetryPolicies::default(), false);
+
+        let result = recovery.handle_error(&classify(Errno::ENOSPC), Path::new("/origen/a"), 0).await;
+        assert!(matches!(result, Err(CopyError::DiskFull(_))));
+
+        let result = recovery.handle_error(&CopyError::Cancelled, Path::new("/origen/a"), 0).await;
+        assert!(matches!(result, Err(CopyError::Cancelled)));
     }
 }
\ No newline at end of file
@@ -0,0 +1,39 @@
+// src/core/helper_protocol.rs
+// Protocolo entre la aplicación y copymaster-helper, el ayudante con privilegios
+// que se lanza con pkexec. Las rutas autorizadas van como argumentos del ayudante;
+// por stdin llega una petición JSON por línea (`Write` seguida de `len` bytes en
+// bruto) y por stdout sale una respuesta JSON por línea.
+//
+// El ayudante compila este archivo por su cuenta, así que no debe depender de
+// nada más del crate.
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Tope de cada escritura, para que el ayudante no reserve memoria sin límite
+pub const MAX_WRITE_LEN: u64 = 4 * 1024 * 1024;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum HelperRequest {
+    /// Crear un directorio que no existe
+    CreateDir { path: PathBuf, mode: u32 },
+    /// Crear un archivo que no existe (O_EXCL) y dejarlo abierto
+    CreateFile { path: PathBuf, mode: u32 },
+    /// Escribir los `len` bytes que siguen a la línea en un archivo creado en esta sesión
+    Write { path: PathBuf, offset: u64, len: u64 },
+    SetPermissions { path: PathBuf, mode: u32 },
+    /// Solo hacia el usuario que lanzó pkexec y sus grupos
+    SetOwner { path: PathBuf, uid: u32, gid: u32 },
+    /// Volcar a disco `from`, creado en esta sesión, y renombrarlo a `to`
+    Rename { from: PathBuf, to: PathBuf },
+    Finish,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum HelperResponse {
+    /// Listo para recibir peticiones, o petición completada
+    Ok,
+    /// La operación falló en el sistema
+    Failed { os_error: Option<i32>, message: String },
+    /// La petición está fuera de lo autorizado
+    Refused(String),
+}
@@ -0,0 +1,483 @@
+// src/core/journal.rs
+// Diario en disco de cada transferencia, para poder reanudarla tras un cierre inesperado
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::core::device_queue::{ItemTransferStatus, QueuePriority, TransferItem, TransferJob, TransferStatus};
+use crate::core::error_recovery::{CopyError, IoOperation, IoResultExt};
+
+/// Tamaño mínimo del registro de eventos antes de pasarlo a la cabecera
+const MIN_COMPACT_LOG_BYTES: u64 = 1024 * 1024;
+
+/// Estado de un archivo de destino según el diario
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FileJournalState {
+    /// Escrito hasta `offset`; lo anterior se comprueba de nuevo al reanudar.
+    /// Con offset 0 solo indica que el destino lo creó esta transferencia.
+    Partial { offset: u64 },
+    Completed,
+}
+
+/// Línea del registro de eventos
+#[derive(Debug, Serialize, Deserialize)]
+struct FileEvent {
+    dest: PathBuf,
+    state: FileJournalState,
+}
+
+/// Contenido del diario de una transferencia
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalRecord {
+    pub job_id: u64,
+    pub transfer_name: String,
+    pub destination: PathBuf,
+    pub priority: QueuePriority,
+    pub status: TransferStatus,
+    pub root_items: Vec<TransferItem>,
+    pub total_size: u64,
+    pub files: HashMap<PathBuf, FileJournalState>, // Por ruta de destino
+    pub updated_at: u64,                           // Segundos desde epoch
+    #[serde(default)]
+    pub options: crate::core::drag_drop::TransferOptions,
+}
+
+impl JournalRecord {
+    /// Bytes ya copiados según el diario
+    pub fn copied_size(&self) -> u64 {
+        self.files.values().map(|state| match state {
+            FileJournalState::Partial { offset } => *offset,
+            FileJournalState::Completed => 0,
+        }).sum::<u64>()
+            + Self::completed_size(&self.root_items, &self.destination, &self.files)
+    }
+
+    fn completed_size(items: &[TransferItem], destination: &Path, files: &HashMap<PathBuf, FileJournalState>) -> u64 {
+        items.iter().map(|item| {
+            let own = match files.get(&destination.join(&item.relative_path)) {
+                Some(FileJournalState::Completed) if item.children.is_empty() => item.size,
+                _ => 0,
+            };
+            own + Self::completed_size(&item.children, destination, files)
+        }).sum()
+    }
+
+    /// Temporales de los archivos que quedaron a medias
+    pub fn part_files(&self) -> Vec<PathBuf> {
+        self.files
+            .iter()
+            .filter(|(_, state)| matches!(state, FileJournalState::Partial { .. }))
+            .map(|(dest, _)| crate::core::atomic_write::part_path(dest))
+            .collect()
+    }
+
+    /// Reconstruir el job a partir del diario, en pausa y con el estado de cada elemento
+    pub fn restore_job(&self) -> TransferJob {
+        let mut root_items = self.root_items.clone();
+        for item in root_items.iter_mut() {
+            Self::restore_status(item, &self.destination, &self.files);
+        }
+
+        let mut job = TransferJob::new(
+            self.job_id,
+            root_items,
+            self.destination.clone(),
+            self.transfer_name.clone(),
+            self.priority.clone(),
+        );
+        job.status = TransferStatus::Paused;
+        job.rate_limiter.set_limit_mbps(self.options.bandwidth_limit_mbps);
+        job.options = self.options.clone();
+        job.total_size = self.total_size;
+        job.copied_size = self.copied_size();
+        job
+    }
+
+    fn restore_status(item: &mut TransferItem, destination: &Path, files: &HashMap<PathBuf, FileJournalState>) {
+        item.status = match files.get(&destination.join(&item.relative_path)) {
+            Some(FileJournalState::Completed) => ItemTransferStatus::Completed,
+            _ => ItemTransferStatus::Pending,
+        };
+
+        for child in item.children.iter_mut() {
+            Self::restore_status(child, destination, files);
+        }
+    }
+}
+
+/// Diario de una transferencia en curso, compartido entre el motor y el ejecutor.
+///
+/// `<id>.json` guarda el árbol y el estado del job y se reescribe al cambiar de
+/// estado; el avance de cada archivo se añade a `<id>.log`, una línea por evento.
+/// Cuando el registro ocupa más que la cabecera se vuelca en ella y se vacía,
+/// así el coste de reescribirla se reparte entre los eventos.
+pub struct TransferJournal {
+    path: PathBuf,
+    record: Mutex<JournalRecord>,
+    log: Mutex<Option<File>>,
+    log_bytes: AtomicU64,  // Escritos en el registro desde el último volcado
+    compact_at: AtomicU64, // Tamaño del registro que provoca el siguiente volcado
+}
+
+impl TransferJournal {
+    /// Directorio donde se guardan los diarios
+    pub fn journal_dir() -> Option<PathBuf> {
+        crate::core::config::ConfigManager::config_dir().map(|dir| dir.join("journal"))
+    }
+
+    /// Crear el diario de un job nuevo, o retomar el existente si el job se está reanudando
+    pub fn for_job(dir: &Path, job: &TransferJob) -> Result<Self, CopyError> {
+        let path = Self::journal_path(dir, job.id);
+
+        if let Ok(record) = Self::read(&path) {
+            let journal = Self::with_record(path, record);
+            journal.flush()?;
+            return Ok(journal);
+        }
+
+        let record = JournalRecord {
+            job_id: job.id,
+            transfer_name: job.transfer_name.clone(),
+            destination: job.destination.clone(),
+            priority: job.priority.clone(),
+            status: TransferStatus::Queued,
+            root_items: job.root_items.clone(),
+            total_size: job.total_size,
+            files: HashMap::new(),
+            updated_at: now_secs(),
+            options: job.options.clone(),
+        };
+
+        std::fs::create_dir_all(dir).io_context(IoOperation::CreateDir, dir)?;
+        let journal = Self::with_record(path, record);
+        journal.flush()?;
+        Ok(journal)
+    }
+
+    /// Diarios de transferencias que no llegaron a terminar
+    pub fn interrupted(dir: &Path) -> Vec<JournalRecord> {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return Vec::new();
+        };
+
+        let mut records: Vec<JournalRecord> = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().map(|ext| ext == "json").unwrap_or(false))
+            .filter_map(|path| match Self::read(&path) {
+                Ok(record) => Some(record),
+                Err(e) => {
+                    log::warn!("Diario ilegible {}: {}", path.display(), e);
+                    None
+                }
+            })
+            .filter(|record| !matches!(record.status, TransferStatus::Completed | TransferStatus::Cancelled))
+            .collect();
+
+        records.sort_by_key(|record| record.updated_at);
+        records
+    }
+
+    /// Borrar el diario de un job que no se va a reanudar
+    pub fn discard(dir: &Path, job_id: u64) -> Result<(), CopyError> {
+        let path = Self::journal_path(dir, job_id);
+
+        for file in [path.with_extension("log"), path] {
+            match std::fs::remove_file(&file) {
+                Ok(()) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(CopyError::from_io(e, IoOperation::Remove, &file)),
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn job_id(&self) -> u64 {
+        self.lock().job_id
+    }
+
+    /// Offset desde el que se puede continuar `dest`, o 0 si no hay copia parcial
+    pub fn partial_offset(&self, dest: &Path) -> u64 {
+        match self.lock().files.get(dest) {
+            Some(FileJournalState::Partial { offset }) => *offset,
+            _ => 0,
+        }
+    }
+
+    pub fn is_completed(&self, dest: &Path) -> bool {
+        matches!(self.lock().files.get(dest), Some(FileJournalState::Completed))
+    }
+
+    /// Si el destino existente lo creó esta misma transferencia
+    pub fn owns(&self, dest: &Path) -> bool {
+        self.lock().files.contains_key(dest)
+    }
+
+    /// Anotar que se va a crear `dest`, antes de tocarlo
+    pub fn start_file(&self, dest: &Path) {
+        if !self.owns(dest) {
+            self.set_file(dest, FileJournalState::Partial { offset: 0 });
+        }
+    }
+
+    /// Registrar hasta dónde se ha escrito `dest`
+    pub fn record_offset(&self, dest: &Path, offset: u64) {
+        self.set_file(dest, FileJournalState::Partial { offset });
+    }
+
+    /// Lo escrito en `dest` no es fiable: se vuelve a copiar desde el principio
+    pub fn reset_file(&self, dest: &Path) {
+        self.set_file(dest, FileJournalState::Partial { offset: 0 });
+    }
+
+    pub fn mark_completed(&self, dest: &Path) {
+        self.set_file(dest, FileJournalState::Completed);
+    }
+
+    /// Añadir elementos soltados sobre la transferencia ya empezada
+    pub fn add_root_items(&self, items: &[TransferItem]) {
+        {
+            let mut record = self.lock();
+            record.total_size += items.iter().map(|item| item.size).sum::<u64>();
+            record.root_items.extend_from_slice(items);
+        }
+        if let Err(e) = self.flush() {
+            log::warn!("No se pudo guardar el diario de la transferencia: {}", e);
+        }
+    }
+
+    pub fn set_status(&self, status: TransferStatus) {
+        self.lock().status = status;
+        if let Err(e) = self.flush() {
+            log::warn!("No se pudo guardar el diario de la transferencia: {}", e);
+        }
+    }
+
+    /// Eliminar el diario cuando la transferencia termina
+    pub fn finish(&self) {
+        *self.log.lock().unwrap_or_else(|p| p.into_inner()) = None;
+
+        for file in [self.path.with_extension("log"), self.path.clone()] {
+            if let Err(e) = std::fs::remove_file(&file) {
+                log::warn!("No se pudo borrar el diario {}: {}", file.display(), e);
+            }
+        }
+    }
+
+    /// Reescribir la cabecera con el estado de todos los archivos y vaciar el registro.
+    /// La cabecera se escribe con write + rename para no dejarla a medias.
+    pub fn flush(&self) -> Result<(), CopyError> {
+        let record = self.lock();
+        let mut log_file = self.log.lock().unwrap_or_else(|p| p.into_inner());
+
+        let mut snapshot = record.clone();
+        snapshot.updated_at = now_secs();
+        let content = serde_json::to_vec(&snapshot).map_err(|e| CopyError::Io(e.to_string()))?;
+
+        let header_bytes = content.len() as u64;
+        let tmp = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp, content).io_context(IoOperation::Write, &tmp)?;
+        std::fs::rename(&tmp, &self.path).io_context(IoOperation::Rename, &self.path)?;
+
+        let log_path = self.path.with_extension("log");
+        let file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(&log_path)
+            .io_context(IoOperation::Create, &log_path)?;
+        *log_file = Some(file);
+        self.log_bytes.store(0, Ordering::SeqCst);
+        self.compact_at.store(header_bytes.max(MIN_COMPACT_LOG_BYTES), Ordering::SeqCst);
+
+        Ok(())
+    }
+
+    fn set_file(&self, dest: &Path, state: FileJournalState) {
+        let compact = {
+            let mut record = self.lock();
+            record.files.insert(dest.to_path_buf(), state);
+
+            // Se escribe con el registro bloqueado para que las líneas queden en orden
+            let mut log_file = self.log.lock().unwrap_or_else(|p| p.into_inner());
+            let Some(file) = log_file.as_mut() else {
+                return;
+            };
+
+            let event = FileEvent { dest: dest.to_path_buf(), state };
+            let written = serde_json::to_vec(&event)
+                .map_err(std::io::Error::from)
+                .and_then(|mut line| {
+                    line.push(b'\n');
+                    file.write_all(&line).map(|_| line.len() as u64)
+                });
+
+            match written {
+                Ok(len) => self.log_bytes.fetch_add(len, Ordering::SeqCst) + len >= self.compact_at.load(Ordering::SeqCst),
+                Err(e) => {
+                    log::warn!("No se pudo escribir en el diario {}: {}", self.path.display(), e);
+                    false
+                }
+            }
+        };
+
+        if compact {
+            if let Err(e) = self.flush() {
+                log::warn!("No se pudo compactar el diario {}: {}", self.path.display(), e);
+            }
+        }
+    }
+
+    fn with_record(path: PathBuf, record: JournalRecord) -> Self {
+        Self {
+            path,
+            record: Mutex::new(record),
+            log: Mutex::new(None),
+            log_bytes: AtomicU64::new(0),
+            compact_at: AtomicU64::new(MIN_COMPACT_LOG_BYTES),
+        }
+    }
+
+    fn journal_path(dir: &Path, job_id: u64) -> PathBuf {
+        dir.join(format!("{}.json", job_id))
+    }
+
+    /// Leer la cabecera y aplicar los eventos registrados después
+    fn read(path: &Path) -> Result<JournalRecord, CopyError> {
+        let content = std::fs::read(path).io_context(IoOperation::Read, path)?;
+        let mut record: JournalRecord = serde_json::from_slice(&content).map_err(|e| CopyError::Io(e.to_string()))?;
+
+        if let Ok(log) = File::open(path.with_extension("log")) {
+            // Una última línea cortada por el cierre inesperado simplemente se ignora
+            for line in BufReader::new(log).lines().map_while(Result::ok) {
+                let Ok(event) = serde_json::from_str::<FileEvent>(&line) else {
+                    break;
+                };
+                record.files.insert(event.dest, event.state);
+            }
+        }
+
+        Ok(record)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, JournalRecord> {
+        self.record.lock().unwrap_or_else(|p| p.into_inner())
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::test_support::ScratchDir;
+
+    fn file(name: &str, size: u64) -> TransferItem {
+        TransferItem {
+            source_path: PathBuf::from("/origen").join(name),
+            relative_path: PathBuf::from(name),
+            item_type: crate::core::device_queue::ItemType::File,
+            size,
+            children: Vec::new(),
+            status: ItemTransferStatus::Pending,
+            link_target: None,
+            hardlink_key: None,
+        }
+    }
+
+    fn job(id: u64) -> TransferJob {
+        TransferJob::new(id, Vec::new(), PathBuf::from("/destino"), format!("job {}", id), QueuePriority::Normal)
+    }
+
+    #[test]
+    fn interrupted_replays_logged_offsets() {
+        let dir = ScratchDir::new("journal-replay");
+        let dest = Path::new("/destino/a");
+        {
+            let journal = TransferJournal::for_job(&dir, &job(1)).unwrap();
+            journal.set_status(TransferStatus::Copying);
+            journal.start_file(dest);
+            // Solo queda en el registro de eventos, no en la cabecera
+            journal.record_offset(dest, 42);
+        }
+
+        let records = TransferJournal::interrupted(&dir);
+
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].job_id, 1);
+        assert_eq!(records[0].files.get(dest), Some(&FileJournalState::Partial { offset: 42 }));
+        assert_eq!(records[0].part_files(), [crate::core::atomic_write::part_path(dest)]);
+    }
+
+    #[test]
+    fn finished_transfers_are_not_interrupted() {
+        let dir = ScratchDir::new("journal-finished");
+        TransferJournal::for_job(&dir, &job(1)).unwrap().set_status(TransferStatus::Completed);
+        TransferJournal::for_job(&dir, &job(2)).unwrap().set_status(TransferStatus::Cancelled);
+        TransferJournal::for_job(&dir, &job(3)).unwrap().set_status(TransferStatus::Paused);
+        TransferJournal::for_job(&dir, &job(4)).unwrap().finish();
+
+        let ids: Vec<_> = TransferJournal::interrupted(&dir).iter().map(|record| record.job_id).collect();
+
+        assert_eq!(ids, [3]);
+        TransferJournal::discard(&dir, 3).unwrap();
+        assert!(TransferJournal::interrupted(&dir).is_empty());
+    }
+
+    #[test]
+    fn compaction_keeps_every_file_state() {
+        let dir = ScratchDir::new("journal-compact");
+        let journal = TransferJournal::for_job(&dir, &job(1)).unwrap();
+        let log = dir.join("1.log");
+        let dest = |i: u32| PathBuf::from(format!("/destino/archivo-{:05}", i));
+
+        let mut largest_log = 0;
+        for i in 0..20_000 {
+            journal.start_file(&dest(i));
+            journal.record_offset(&dest(i), 4096);
+            journal.mark_completed(&dest(i));
+            largest_log = largest_log.max(std::fs::metadata(&log).unwrap().len());
+        }
+        journal.record_offset(&dest(20_000), 7);
+
+        // El registro se vuelca en la cabecera en lugar de crecer sin límite
+        assert!(largest_log <= MIN_COMPACT_LOG_BYTES.max(std::fs::metadata(dir.join("1.json")).unwrap().len()) + 1024);
+        drop(journal);
+        let record = TransferJournal::read(&dir.join("1.json")).unwrap();
+        assert_eq!(record.files.len(), 20_001);
+        assert!((0..20_000).all(|i| record.files.get(&dest(i)) == Some(&FileJournalState::Completed)));
+        assert_eq!(record.files.get(&dest(20_000)), Some(&FileJournalState::Partial { offset: 7 }));
+    }
+
+    #[test]
+    fn completed_files_count_their_size() {
+        let record = JournalRecord {
+            job_id: 1,
+            transfer_name: "job".to_string(),
+            destination: PathBuf::from("/destino"),
+            priority: QueuePriority::Normal,
+            status: TransferStatus::Copying,
+            root_items: vec![file("a", 100), file("b", 50)],
+            total_size: 150,
+            files: HashMap::from([
+                (PathBuf::from("/destino/a"), FileJournalState::Completed),
+                (PathBuf::from("/destino/b"), FileJournalState::Partial { offset: 20 }),
+            ]),
+            updated_at: 0,
+            options: Default::default(),
+        };
+
+        assert_eq!(record.copied_size(), 120);
+        let restored = record.restore_job();
+        assert!(matches!(restored.root_items[0].status, ItemTransferStatus::Completed));
+        assert!(matches!(restored.root_items[1].status, ItemTransferStatus::Pending));
+    }
+}
@@ -0,0 +1,93 @@
+// src/core/kernel_copy.rs
+// Rutas de copia que delegan el trabajo en el kernel
+use std::fs::File;
+use std::os::unix::io::AsRawFd;
+
+use nix::errno::Errno;
+use nix::sys::statfs::{fstatfs, BTRFS_SUPER_MAGIC, XFS_SUPER_MAGIC};
+
+use crate::core::parallel_copy::ChunkRange;
+
+/// Tamaño de cada llamada a copy_file_range, para poder informar del progreso
+const COPY_RANGE_STEP: usize = 8 * 1024 * 1024;
+
+// FICLONE = _IOW(0x94, 9, int)
+nix::ioctl_write_int!(ioctl_ficlone, 0x94, 9);
+
+/// Resultado de intentar una ruta acelerada
+pub enum KernelCopyOutcome {
+    /// El kernel copió todo
+    Done,
+    /// La ruta no está disponible aquí; probar la siguiente
+    Unsupported,
+}
+
+/// Clonar los extents del origen (reflink). Solo se intenta si ambos archivos
+/// están en btrfs o XFS; el kernel rechaza el clon si no son el mismo sistema de archivos.
+pub fn try_reflink(src: &File, dst: &File) -> Result<KernelCopyOutcome, Errno> {
+    let (Ok(src_fs), Ok(dst_fs)) = (fstatfs(src), fstatfs(dst)) else {
+        return Ok(KernelCopyOutcome::Unsupported);
+    };
+
+    let fs_type = src_fs.filesystem_type();
+    let supports_reflink = fs_type == BTRFS_SUPER_MAGIC || fs_type == XFS_SUPER_MAGIC;
+    if !supports_reflink || dst_fs.filesystem_type() != fs_type {
+        return Ok(KernelCopyOutcome::Unsupported);
+    }
+
+    match unsafe { ioctl_ficlone(dst.as_raw_fd(), src.as_raw_fd() as _) } {
+        Ok(_) => Ok(KernelCopyOutcome::Done),
+        // Distinto sistema de archivos, XFS sin reflink=1, etc.
+        Err(Errno::EXDEV | Errno::EOPNOTSUPP | Errno::EINVAL | Errno::ENOTTY) => Ok(KernelCopyOutcome::Unsupported),
+        Err(e) => Err(e),
+    }
+}
+
+/// Copiar los rangos indicados con copy_file_range, sin pasar por espacio de usuario.
+/// Si el kernel no lo soporta entre estos archivos antes de copiar nada, se
+/// devuelve `Unsupported` para usar la copia por chunks. `on_progress` recibe
+/// los bytes de cada llamada y hasta dónde está copiado el destino, ya que los
+/// rangos se copian en orden; si devuelve error la copia se detiene y se devuelve tal cual.
+pub fn try_copy_file_range(
+    src: &File,
+    dst: &File,
+    ranges: &[ChunkRange],
+    mut on_progress: impl FnMut(u64, u64) -> Result<(), Errno>,
+) -> Result<KernelCopyOutcome, Errno> {
+    let mut copied_any = false;
+
+    for range in ranges {
+        let end = range.offset + range.len;
+        let mut offset = range.offset;
+
+        while offset < end {
+            let mut off_in = offset as i64;
+            let mut off_out = offset as i64;
+            let want = ((end - offset) as usize).min(COPY_RANGE_STEP);
+
+            let copied = match nix::fcntl::copy_file_range(
+                src.as_raw_fd(),
+                Some(&mut off_in),
+                dst.as_raw_fd(),
+                Some(&mut off_out),
+                want,
+            ) {
+                // Algunos sistemas de archivos (procfs, sysfs...) devuelven 0 sin copiar
+                Ok(0) if !copied_any => return Ok(KernelCopyOutcome::Unsupported),
+                Ok(0) => return Err(Errno::EIO),
+                Ok(n) => n as u64,
+                Err(Errno::EINTR) => continue,
+                Err(Errno::EXDEV | Errno::ENOSYS | Errno::EOPNOTSUPP | Errno::EINVAL) if !copied_any => {
+                    return Ok(KernelCopyOutcome::Unsupported);
+                }
+                Err(e) => return Err(e),
+            };
+
+            copied_any = true;
+            offset += copied;
+            on_progress(copied, offset)?;
+        }
+    }
+
+    Ok(KernelCopyOutcome::Done)
+}
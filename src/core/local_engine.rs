@@ -15,17 +15,154 @@ pub trait CopySystem: Send + Sync {
 
 pub struct LocalCopySystem {
     engine: LocalCopyEngine,
-    device_manager: crate::core::device_detector::DeviceMonitor,
+    device_manager: Mutex<crate::core::device_detector::DeviceMonitor>,
     options: crate::core::copy_engine::CopyOptions,
 }
 
+impl LocalCopySystem {
+    pub fn new(options: crate::core::copy_engine::CopyOptions) -> Self {
+        Self {
+            engine: LocalCopyEngine::new(options.clone()),
+            device_manager: Mutex::new(crate::core::device_detector::DeviceMonitor::new()),
+            options,
+        }
+    }
+    
+    /// Obedecer a un control superior, como el de una cola de dispositivo
+    pub fn with_parent_control(mut self, parent: &Arc<crate::core::control::TransferControl>) -> Self {
+        self.engine = self.engine.with_control(crate::core::control::TransferControl::child(parent));
+        self
+    }
+}
+
+#[async_trait]
+impl CopySystem for LocalCopySystem {
+    async fn copy(&self, source: &PathBuf, dest: &PathBuf) -> Result<crate::core::copy_engine::CopyStats, crate::core::error_recovery::CopyError> {
+        // El destino aún no existe: se mira el directorio que lo contendrá
+        let target = dest.parent().map(PathBuf::from).unwrap_or_else(|| dest.clone());
+        let device_info = self.device_manager.lock().await.detect_device(&target).await;
+        
+        self.engine.copy_file_optimized(source, dest, &device_info).await
+    }
+    
+    async fn pause(&self) -> Result<(), crate::core::error_recovery::CopyError> {
+        self.engine.control().pause();
+        Ok(())
+    }
+    
+    async fn resume(&self) -> Result<(), crate::core::error_recovery::CopyError> {
+        self.engine.control().resume();
+        Ok(())
+    }
+    
+    async fn cancel(&self) -> Result<(), crate::core::error_recovery::CopyError> {
+        self.engine.control().cancel();
+        Ok(())
+    }
+    
+    fn get_stats(&self) -> crate::core::copy_engine::CopyStats {
+        self.engine.get_stats()
+    }
+    
+    fn get_options(&self) -> &crate::core::copy_engine::CopyOptions {
+        &self.options
+    }
+}
+
+/// Alineación para O_DIRECT cuando no se conoce el dispositivo de destino
+const DEFAULT_BLOCK_SIZE: u64 = 4096;
+
 pub struct LocalCopyEngine {
     options: crate::core::copy_engine::CopyOptions,
+    stats: Arc<std::sync::Mutex<crate::core::copy_engine::CopyStats>>,
+    throttle: crate::core::throttle::Throttle,
+    journal: Option<Arc<crate::core::journal::TransferJournal>>,
+    control: Arc<crate::core::control::TransferControl>,
+    tuner: Option<Arc<crate::core::optimizer::AdaptiveTuner>>, // Compartido por todos los archivos del motor
+    recovery: Arc<crate::core::error_recovery::ErrorRecovery>,
+    // Temporales conservados tras llenarse el disco, con su offset, por si no hay diario
+    kept_partials: std::sync::Mutex<KeptPartials>,
+}
+
+#[derive(Default)]
+struct KeptPartials(std::collections::HashMap<PathBuf, u64>);
+
+impl Drop for KeptPartials {
+    fn drop(&mut self) {
+        // Con el motor se acaba la espera: los temporales ya no están en uso
+        for dest in self.0.keys() {
+            crate::core::atomic_write::mark_inactive(&crate::core::atomic_write::part_path(dest));
+        }
+    }
 }
 
 impl LocalCopyEngine {
     pub fn new(options: crate::core::copy_engine::CopyOptions) -> Self {
-        Self { options }
+        // El límite propio del motor (p. ej. el de la estrategia) se suma al global
+        let mut throttle = crate::core::throttle::Throttle::default()
+            .with_limiter(crate::core::throttle::global_limiter());
+        if options.throttle_mbps.is_some() {
+            throttle = throttle.with_limiter(Arc::new(crate::core::throttle::RateLimiter::new(options.throttle_mbps)));
+        }
+        
+        Self {
+            stats: Arc::new(std::sync::Mutex::new(crate::core::copy_engine::CopyStats::new())),
+            throttle,
+            journal: None,
+            // Sin control propio del job, al menos se obedece al global
+            control: crate::core::control::TransferControl::child(&crate::core::control::global_control()),
+            tuner: options.adaptive_tuning.then(|| {
+                Arc::new(crate::core::optimizer::AdaptiveTuner::new(options.buffer_size, options.max_threads))
+            }),
+            recovery: Arc::new(crate::core::error_recovery::ErrorRecovery::from_policies(
+                crate::core::error_recovery::RetryPolicies::default(),
+                false,
+            )),
+            kept_partials: std::sync::Mutex::new(KeptPartials::default()),
+            options,
+        }
+    }
+    
+    /// Añadir un limitador más, como el de la transferencia o el de la cola del dispositivo
+    pub fn with_rate_limiter(mut self, limiter: Arc<crate::core::throttle::RateLimiter>) -> Self {
+        self.throttle = self.throttle.with_limiter(limiter);
+        self
+    }
+    
+    /// Registrar el avance de cada archivo en el diario de la transferencia
+    pub fn with_journal(mut self, journal: Arc<crate::core::journal::TransferJournal>) -> Self {
+        self.journal = Some(journal);
+        self
+    }
+    
+    pub fn journal(&self) -> Option<&Arc<crate::core::journal::TransferJournal>> {
+        self.journal.as_ref()
+    }
+    
+    /// Usar el control del job para pausar y cancelar entre chunks
+    pub fn with_control(mut self, control: Arc<crate::core::control::TransferControl>) -> Self {
+        self.control = control;
+        self
+    }
+    
+    pub fn control(&self) -> &Arc<crate::core::control::TransferControl> {
+        &self.control
+    }
+    
+    /// Políticas de reintento y recuperación para cada archivo
+    pub fn with_error_recovery(mut self, recovery: Arc<crate::core::error_recovery::ErrorRecovery>) -> Self {
+        self.recovery = recovery;
+        self
+    }
+    
+    pub fn get_options(&self) -> &crate::core::copy_engine::CopyOptions {
+        &self.options
+    }
+    
+    pub fn get_stats(&self) -> crate::core::copy_engine::CopyStats {
+        let mut stats = self.stats.lock().unwrap_or_else(|p| p.into_inner()).clone();
+        stats.tuning = self.tuner.as_ref().map(|tuner| tuner.report());
+        stats
     }
     
     pub async fn copy_file_optimized(
@@ -34,114 +171,399 @@ impl LocalCopyEngine {
         dest: &PathBuf,
         device_info: &crate::core::device_detector::DeviceInfo,
     ) -> Result<crate::core::copy_engine::CopyStats, crate::core::error_recovery::CopyError> {
-        // Implementar copia optimizada
-        match std::fs::copy(source, dest) {
-            Ok(_) => {
-                let stats = crate::core::copy_engine::CopyStats::new();
-                Ok(stats)
+        let size = std::fs::metadata(source)
+            .map_err(|e| crate::core::error_recovery::CopyError::from_io(e, crate::core::error_recovery::IoOperation::Metadata, source))?
+            .len();
+        
+        // Las estadísticas se acumulan entre archivos del mismo motor
+        let (bytes_before, total_before) = {
+            let mut stats = self.stats.lock().unwrap_or_else(|p| p.into_inner());
+            let before = (stats.bytes_transferred, stats.total_bytes);
+            stats.total_bytes += size;
+            stats.logical_bytes += size;
+            stats.files_total += 1;
+            before
+        };
+        
+        if let Err(e) = self.copy_data(source, dest, device_info.block_size).await {
+            // El archivo se volverá a contar si se reintenta o se reanuda
+            let mut stats = self.stats.lock().unwrap_or_else(|p| p.into_inner());
+            stats.bytes_transferred = bytes_before;
+            stats.total_bytes = total_before;
+            stats.logical_bytes -= size;
+            stats.files_total -= 1;
+            return Err(e);
+        }
+        
+        let mut stats = self.stats.lock().unwrap_or_else(|p| p.into_inner());
+        stats.files_copied += 1;
+        Ok(stats.clone())
+    }
+    
+    /// Repetir a través del ayudante con privilegios la copia de los archivos que
+    /// fallaron por permisos, todos en una sesión con una sola autenticación.
+    /// Se escriben desde cero, sin O_DIRECT ni verificación, pero respetando
+    /// límite de ancho de banda, pausa y cancelación. Un resultado por archivo.
+    pub async fn copy_files_elevated(
+        &self,
+        files: &[(PathBuf, PathBuf)],
+    ) -> Vec<Result<(), crate::core::error_recovery::CopyError>> {
+        let sizes: Vec<u64> = files
+            .iter()
+            .map(|(source, _)| std::fs::metadata(source).map(|m| m.len()).unwrap_or(0))
+            .collect();
+        {
+            let mut stats = self.stats.lock().unwrap_or_else(|p| p.into_inner());
+            stats.total_bytes += sizes.iter().sum::<u64>();
+            stats.logical_bytes += sizes.iter().sum::<u64>();
+            stats.files_total += files.len();
+        }
+        let pairs = files.to_vec();
+        let stats = self.stats.clone();
+        let throttle = self.throttle.clone();
+        let control = self.control.clone();
+        
+        let results = tokio::task::spawn_blocking(move || {
+            // Lo escrito de cada archivo, para descontarlo si falla
+            let mut written = vec![0u64; pairs.len()];
+            let results = crate::core::elevation::copy_files(&pairs, |index, bytes| {
+                throttle.acquire(bytes);
+                written[index] += bytes;
+                stats.lock().unwrap_or_else(|p| p.into_inner()).record_bytes(bytes);
+                control.checkpoint()
+            });
+            (results, written)
+        })
+            .await;
+        let (results, written) = match results {
+            Ok(done) => done,
+            Err(e) => (
+                files.iter().map(|_| Err(crate::core::error_recovery::CopyError::Io(e.to_string()))).collect(),
+                vec![0; files.len()],
+            ),
+        };
+        
+        let mut outcome = Vec::with_capacity(files.len());
+        for (((source, dest), result), (size, written)) in files.iter().zip(results).zip(sizes.into_iter().zip(written)) {
+            match result {
+                Ok(_) => {
+                    // El archivo ya es del usuario: fechas y xattrs se aplican sin privilegios
+                    if self.options.preserve_attributes {
+                        self.apply_attributes(source, dest);
+                    }
+                    let mut stats = self.stats.lock().unwrap_or_else(|p| p.into_inner());
+                    stats.file_methods.push((dest.clone(), crate::core::copy_engine::CopyMethod::Chunked));
+                    stats.files_copied += 1;
+                    outcome.push(Ok(()));
+                }
+                Err(e) => {
+                    // Lo escrito en el intento no cuenta
+                    let mut stats = self.stats.lock().unwrap_or_else(|p| p.into_inner());
+                    stats.bytes_transferred = stats.bytes_transferred.saturating_sub(written);
+                    stats.total_bytes = stats.total_bytes.saturating_sub(size);
+                    stats.logical_bytes = stats.logical_bytes.saturating_sub(size);
+                    stats.files_total = stats.files_total.saturating_sub(1);
+                    outcome.push(Err(e));
+                }
             }
-            Err(e) => Err(crate::core::error_recovery::CopyError::Io(e.to_string())),
         }
+        outcome
     }
     
-    // NUEVO MÉTODO: Copiar con manejo de conflictos
-    pub async fn copy_with_conflict_handling(
+    /// Copiar el contenido de un archivo con el algoritmo configurado.
+    /// Se escribe en un temporal oculto que solo se renombra a `dest` cuando datos,
+    /// atributos y verificación están completos. Los fallos se reintentan según
+    /// `ErrorRecovery`: desde el último offset bueno, o desde cero si falló la
+    /// verificación. `block_size` es la alineación que se usa con O_DIRECT.
+    async fn copy_data(
         &self,
         source: &PathBuf,
         dest: &PathBuf,
-        job: &crate::core::device_queue::TransferJob,
-        config: &crate::core::config::AppConfig,
-    ) -> Result<(), crate::core::error_recovery::CopyError> {
-        // Verificar si el archivo de destino ya existe
-        if dest.exists() {
-            // Clonar job para poder mutarlo
-            let mut job_mut = job.clone();
-            
-            // Notificar conflicto
-            let _ = job.progress_sender.send(
-                crate::core::device_queue::TransferProgress::ConflictDetected {
-                    source: source.clone(),
-                    destination: dest.clone(),
+        block_size: u64,
+    ) -> Result<u64, crate::core::error_recovery::CopyError> {
+        let (bytes_before, total_before) = {
+            let stats = self.stats.lock().unwrap_or_else(|p| p.into_inner());
+            (stats.bytes_transferred, stats.total_bytes)
+        };
+        let part = crate::core::atomic_write::part_path(dest);
+        crate::core::atomic_write::mark_active(&part);
+        let kept = self.kept_partials.lock().unwrap_or_else(|p| p.into_inner()).0.remove(dest);
+        // Hasta dónde está escrito el temporal sin huecos; lo actualiza cada pasada
+        let resume = Arc::new(std::sync::atomic::AtomicU64::new(
+            match &self.journal {
+                Some(journal) => journal.partial_offset(dest),
+                None => kept.unwrap_or(0),
+            },
+        ));
+        let mut attempt = 0;
+        
+        let copied = loop {
+            let attempt_started = std::time::Instant::now();
+            let error = match self.copy_data_once(source, dest, &part, block_size, &resume).await {
+                Ok(copied) => {
+                    self.stats.lock().unwrap_or_else(|p| p.into_inner()).active_time += attempt_started.elapsed();
+                    break Ok(copied);
                 }
-            ).await;
+                Err(error) => error,
+            };
             
-            // Manejar conflicto
-            let result = job_mut.handle_conflict(source, dest, config).await;
+            if let Err(e) = self.recovery.handle_error(&error, source, attempt).await {
+                break Err(e);
+            }
+            attempt += 1;
             
-            match result {
-                crate::core::device_queue::ConflictResolutionResult::Overwrite => {
-                    // Sobrescribir archivo
-                    std::fs::copy(source, dest)
-                        .map_err(|e| crate::core::error_recovery::CopyError::Io(e.to_string()))?;
-                    
-                    // Notificar resolución
-                    let _ = job.progress_sender.send(
-                        crate::core::device_queue::TransferProgress::ConflictResolved {
-                            source: source.clone(),
-                            destination: dest.clone(),
-                            action: "Sobrescrito".to_string(),
-                        }
-                    ).await;
-                }
-                crate::core::device_queue::ConflictResolutionResult::Skip => {
-                    // Saltar archivo
-                    let _ = job.progress_sender.send(
-                        crate::core::device_queue::TransferProgress::ConflictResolved {
-                            source: source.clone(),
-                            destination: dest.clone(),
-                            action: "Saltado".to_string(),
-                        }
-                    ).await;
-                    return Ok(());
-                }
-                crate::core::device_queue::ConflictResolutionResult::RenameNew => {
-                    // Renombrar el nuevo archivo
-                    let new_dest = self.generate_unique_filename(dest).await;
-                    std::fs::copy(source, &new_dest)
-                        .map_err(|e| crate::core::error_recovery::CopyError::Io(e.to_string()))?;
-                    
-                    let _ = job.progress_sender.send(
-                        crate::core::device_queue::TransferProgress::ConflictResolved {
-                            source: source.clone(),
-                            destination: new_dest.clone(),
-                            action: "Renombrado nuevo".to_string(),
-                        }
-                    ).await;
+            // Lo escrito no es fiable tras un hash distinto: el reintento empieza de cero
+            if let crate::core::error_recovery::CopyError::HashMismatch = error {
+                resume.store(0, std::sync::atomic::Ordering::SeqCst);
+                if let Some(journal) = &self.journal {
+                    journal.reset_file(dest);
                 }
-                crate::core::device_queue::ConflictResolutionResult::RenameOld => {
-                    // Renombrar el archivo existente
-                    let old_dest = self.generate_unique_filename(dest).await;
-                    std::fs::rename(dest, &old_dest)
-                        .map_err(|e| crate::core::error_recovery::CopyError::Io(e.to_string()))?;
-                    
-                    // Copiar el nuevo archivo
-                    std::fs::copy(source, dest)
-                        .map_err(|e| crate::core::error_recovery::CopyError::Io(e.to_string()))?;
-                    
-                    let _ = job.progress_sender.send(
-                        crate::core::device_queue::TransferProgress::ConflictResolved {
-                            source: source.clone(),
-                            destination: dest.clone(),
-                            action: "Renombrado antiguo".to_string(),
-                        }
-                    ).await;
-                }
-                crate::core::device_queue::ConflictResolutionResult::Ask => {
-                    // Necesita intervención del usuario
-                    return Err(crate::core::error_recovery::CopyError::Cancelled);
+            }
+            
+            let resume_offset = resume.load(std::sync::atomic::Ordering::SeqCst);
+            log::warn!(
+                "{} en {} (reintento {}), continuando desde el byte {}",
+                error, dest.display(), attempt, resume_offset
+            );
+            
+            // El reintento vuelve a contar lo que escriba y descuenta lo que reanuda
+            let mut stats = self.stats.lock().unwrap_or_else(|p| p.into_inner());
+            stats.bytes_transferred = bytes_before;
+            stats.total_bytes = total_before;
+            stats.retries.push(crate::core::error_recovery::RetryAttempt {
+                path: source.clone(),
+                attempt,
+                class: error.class(),
+                error: error.to_string(),
+                resume_offset,
+            });
+        };
+        
+        let copied = match copied {
+            Ok(copied) => copied,
+            Err(e) => {
+                // Sin diario no se podrá reanudar, así que el temporal no sirve.
+                // Al cancelar manda la configuración. Con el disco lleno se conserva
+                // siempre: se sigue desde ahí cuando haya espacio.
+                let keep = match e {
+                    crate::core::error_recovery::CopyError::Cancelled => self.options.keep_partial_on_cancel,
+                    crate::core::error_recovery::CopyError::DiskFull(_) => {
+                        let offset = resume.load(std::sync::atomic::Ordering::SeqCst);
+                        self.kept_partials.lock().unwrap_or_else(|p| p.into_inner()).0.insert(dest.clone(), offset);
+                        true
+                    }
+                    _ => self.journal.is_some(),
+                };
+                if !keep {
+                    let _ = std::fs::remove_file(&part);
                 }
-                crate::core::device_queue::ConflictResolutionResult::Cancelled => {
-                    // El usuario canceló
-                    return Err(crate::core::error_recovery::CopyError::Cancelled);
+                // El que espera espacio sigue en uso; los demás quedan al diario
+                if !matches!(e, crate::core::error_recovery::CopyError::DiskFull(_)) {
+                    crate::core::atomic_write::mark_inactive(&part);
                 }
+                return Err(e);
             }
-        } else {
-            // No hay conflicto, copiar normalmente
-            std::fs::copy(source, dest)
-                .map_err(|e| crate::core::error_recovery::CopyError::Io(e.to_string()))?;
+        };
+        
+        // rename conserva permisos, propietario, xattrs y fechas
+        if self.options.preserve_attributes {
+            self.apply_attributes(source, &part);
         }
         
-        Ok(())
+        let committed = crate::core::atomic_write::commit(&part, dest);
+        crate::core::atomic_write::mark_inactive(&part);
+        committed?;
+        
+        Ok(copied)
+    }
+    
+    /// Bytes del temporal de `dest` escritos sin huecos; su tamaño aparente no
+    /// sirve porque se reserva entero antes de escribir
+    pub fn partial_offset(&self, dest: &std::path::Path) -> u64 {
+        let kept = self.kept_partials.lock().unwrap_or_else(|p| p.into_inner()).0.get(dest).copied();
+        match (kept, &self.journal) {
+            (Some(offset), _) => offset,
+            (None, Some(journal)) => journal.partial_offset(dest),
+            (None, None) => 0,
+        }
+    }
+    
+    /// Renunciar al temporal que quedó de `dest`, p. ej. al saltar el archivo
+    pub fn discard_partial(&self, dest: &std::path::Path) {
+        self.kept_partials.lock().unwrap_or_else(|p| p.into_inner()).0.remove(dest);
+        let part = crate::core::atomic_write::part_path(dest);
+        let _ = std::fs::remove_file(&part);
+        crate::core::atomic_write::mark_inactive(&part);
+        if let Some(journal) = &self.journal {
+            journal.reset_file(dest);
+        }
+    }
+    
+    /// Aplicar los atributos del origen; los fallos quedan como avisos en las estadísticas
+    pub fn apply_attributes(&self, source: &std::path::Path, dest: &std::path::Path) {
+        let warnings = crate::core::attributes::copy_attributes(source, dest);
+        
+        if !warnings.is_empty() {
+            let mut stats = self.stats.lock().unwrap_or_else(|p| p.into_inner());
+            for warning in warnings {
+                stats.add_warning(warning);
+            }
+        }
+    }
+    
+    /// Una pasada de copia de `source` al temporal `part`. El diario se lleva por `dest`.
+    async fn copy_data_once(
+        &self,
+        source: &PathBuf,
+        dest: &PathBuf,
+        part: &std::path::Path,
+        block_size: u64,
+        resume: &Arc<std::sync::atomic::AtomicU64>,
+    ) -> Result<u64, crate::core::error_recovery::CopyError> {
+        let sync_mode = self.options.effective_sync_mode();
+        let resume_from = resume.load(std::sync::atomic::Ordering::SeqCst);
+        let plain_copy = resume_from == 0
+            && !self.options.direct_io
+            && sync_mode == crate::core::copy_engine::SyncMode::None
+            && !self.throttle.is_limited();
+        
+        let threads = match self.options.algorithm {
+            crate::core::copy_engine::CopyAlgorithm::Standard if !self.options.verify_after_copy && plain_copy => {
+                // std::fs::copy no se puede interrumpir: la pausa solo se atiende antes de empezar
+                self.control.checkpoint_async().await?;
+                let copied = std::fs::copy(source, part)
+                    .map_err(|e| crate::core::error_recovery::CopyError::from_io(e, crate::core::error_recovery::IoOperation::Copy, part))?;
+                let mut stats = self.stats.lock().unwrap_or_else(|p| p.into_inner());
+                stats.record_bytes(copied);
+                stats.file_methods.push((dest.clone(), crate::core::copy_engine::CopyMethod::Standard));
+                return Ok(copied);
+            }
+            crate::core::copy_engine::CopyAlgorithm::ParallelChunks => self.options.max_threads,
+            // Un solo rango: el hash resultante es el xxh3 del archivo completo
+            crate::core::copy_engine::CopyAlgorithm::Standard |
+            crate::core::copy_engine::CopyAlgorithm::Verified => 1,
+        };
+        
+        let verification = match self.options.algorithm {
+            crate::core::copy_engine::CopyAlgorithm::Verified => Some(self.options.verification_mode),
+            _ if self.options.verify_after_copy => Some(self.options.verification_mode),
+            _ => None,
+        };
+        
+        let copier = crate::core::parallel_copy::ParallelChunkCopier::new(self.options.buffer_size, threads)
+            .with_verification(verification)
+            .with_sparse(self.options.sparse_files)
+            .with_kernel_acceleration(self.options.kernel_acceleration)
+            .with_direct_io(self.options.direct_io.then_some(block_size as usize))
+            .with_sync_mode(sync_mode)
+            .with_throttle(self.throttle.clone())
+            .with_control(self.control.clone())
+            // Con límite de ancho de banda no hay nada que ajustar
+            .with_tuner(self.tuner.clone().filter(|_| !self.throttle.is_limited()));
+        let source = source.clone();
+        let dest_path = dest.clone();
+        let part_path = part.to_path_buf();
+        let stats = self.stats.clone();
+        let journal = self.journal.clone();
+        let resume = resume.clone();
+        
+        // El pool de hilos bloquea, así que no debe correr en el runtime async
+        let result = tokio::task::spawn_blocking(move || {
+            let checkpoint = |offset: u64| {
+                resume.store(offset, std::sync::atomic::Ordering::SeqCst);
+                if let Some(journal) = &journal {
+                    journal.record_offset(&dest_path, offset);
+                }
+            };
+            copier.copy_resumable(&source, &part_path, &stats, resume_from, checkpoint)
+        })
+            .await
+            .map_err(|e| crate::core::error_recovery::CopyError::Io(e.to_string()))??;
+        
+        let mut stats = self.stats.lock().unwrap_or_else(|p| p.into_inner());
+        stats.file_methods.push((dest.clone(), result.method));
+        if let Some(digest) = result.digest {
+            stats.verified_hash = Some(digest);
+        }
+        
+        Ok(result.bytes)
+    }
+    
+    // NUEVO MÉTODO: Copiar con manejo de conflictos
+    pub async fn copy_with_conflict_handling(
+        &self,
+        source: &PathBuf,
+        dest: &PathBuf,
+        job: &crate::core::device_queue::TransferJob,
+        config: &crate::core::config::AppConfig,
+    ) -> Result<(), crate::core::error_recovery::CopyError> {
+        match self.resolve_conflict(source, dest, job, config).await? {
+            Some(target) => self.copy_data(source, &target, DEFAULT_BLOCK_SIZE).await.map(|_| ()),
+            None => Ok(()),
+        }
+    }
+    
+    /// Resolver el conflicto con un `dest` que ya existe. Devuelve dónde hay que
+    /// escribir (`None` si se salta el archivo); con `RenameOld` el existente ya
+    /// está apartado. Los reintentos deben usar esa ruta sin volver a preguntar.
+    pub async fn resolve_conflict(
+        &self,
+        source: &PathBuf,
+        dest: &PathBuf,
+        job: &crate::core::device_queue::TransferJob,
+        config: &crate::core::config::AppConfig,
+    ) -> Result<Option<PathBuf>, crate::core::error_recovery::CopyError> {
+        // Verificar si el archivo de destino ya existe
+        if !dest.exists() {
+            return Ok(Some(dest.clone()));
+        }
+        
+        // Clonar job para poder mutarlo
+        let mut job_mut = job.clone();
+        
+        // Notificar conflicto
+        let _ = job.progress_sender.send(
+            crate::core::device_queue::TransferProgress::ConflictDetected {
+                source: source.clone(),
+                destination: dest.clone(),
+            }
+        ).await;
+        
+        // Manejar conflicto
+        let (target, action) = match job_mut.handle_conflict(source, dest, config).await {
+            crate::core::device_queue::ConflictResolutionResult::Overwrite => (Some(dest.clone()), "Sobrescrito"),
+            crate::core::device_queue::ConflictResolutionResult::Skip => (None, "Saltado"),
+            crate::core::device_queue::ConflictResolutionResult::RenameNew => {
+                // Renombrar el nuevo archivo
+                (Some(self.generate_unique_filename(dest).await), "Renombrado nuevo")
+            }
+            crate::core::device_queue::ConflictResolutionResult::RenameOld => {
+                // Renombrar el archivo existente
+                let old_dest = self.generate_unique_filename(dest).await;
+                std::fs::rename(dest, &old_dest)
+                    .map_err(|e| crate::core::error_recovery::CopyError::from_io(e, crate::core::error_recovery::IoOperation::Rename, dest))?;
+                (Some(dest.clone()), "Renombrado antiguo")
+            }
+            crate::core::device_queue::ConflictResolutionResult::Ask => {
+                // Necesita intervención del usuario
+                return Err(crate::core::error_recovery::CopyError::Cancelled);
+            }
+            crate::core::device_queue::ConflictResolutionResult::Cancelled => {
+                // El usuario canceló
+                return Err(crate::core::error_recovery::CopyError::Cancelled);
+            }
+        };
+        
+        // Notificar resolución
+        let _ = job.progress_sender.send(
+            crate::core::device_queue::TransferProgress::ConflictResolved {
+                source: source.clone(),
+                destination: target.clone().unwrap_or_else(|| dest.clone()),
+                action: action.to_string(),
+            }
+        ).await;
+        
+        Ok(target)
     }
     
     // NUEVO MÉTODO: Generar nombre único para archivos
@@ -1,17 +1,35 @@
 // src/core/mod.rs
+pub mod atomic_write;
+pub mod attributes;
 pub mod autostart;
+pub mod benchmark;
 pub mod config;
+pub mod control;
 pub mod copy_engine;
 pub mod daemon;
 pub mod device_detector;
 pub mod device_queue;
+pub mod direct_io;
+pub mod disk_space;
 pub mod drag_drop;
+pub mod elevation;
 pub mod error_recovery;
 pub mod extensions;
+pub mod helper_protocol;
+pub mod journal;
+pub mod kernel_copy;
 pub mod local_engine;
+pub mod locality;
 pub mod optimizer;
+pub mod parallel_copy;
+pub mod preflight;
 pub mod transfer_manager;
-pub mod queue_manager;
+pub mod tree_copy;
+pub mod scheduler;
+pub mod throttle;
+
+#[cfg(test)]
+pub(crate) mod test_support;
 
 // Re-exportar tipos comunes
 pub use autostart::AutoStartManager;
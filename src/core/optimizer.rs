@@ -1,12 +1,13 @@
 // src/core/optimizer.rs
 use crate::core::device_detector::{DeviceInfo, DeviceType};
-use crate::core::copy_engine::CopyOptions;
+use crate::core::copy_engine::{CopyOptions, SyncMode};
 
 #[derive(Debug, Clone)]
 pub struct CopyStrategy {
     pub buffer_size: usize,
     pub max_threads: usize,
     pub use_direct_io: bool,
+    pub sync_mode: SyncMode,
     pub read_ahead: usize,
     pub throttle_mbps: Option<f64>,
 }
@@ -17,6 +18,7 @@ impl Default for CopyStrategy {
             buffer_size: 65536,
             max_threads: num_cpus::get() * 2,
             use_direct_io: false,
+            sync_mode: SyncMode::None,
             read_ahead: 4,
             throttle_mbps: None,
         }
@@ -30,6 +32,8 @@ impl CopyOptimizer {
         Self
     }
     
+    /// Estrategia inicial según los dispositivos; con `adaptive_tuning`
+    /// el `AdaptiveTuner` del motor la corrige mientras se copia
     pub fn get_optimal_strategy(
         &self,
         source: &DeviceInfo,
@@ -46,12 +50,14 @@ impl CopyOptimizer {
                 }
             }
             (DeviceType::NVMeSSD, DeviceType::USB3) => {
+                // No llenar la caché de escritura más deprisa de lo que el USB la vacía
                 CopyStrategy {
                     buffer_size: 128 * 1024,
                     max_threads: 4,
                     use_direct_io: false,
                     read_ahead: 2,
-                    throttle_mbps: Some(400.0),
+                    throttle_mbps: Some(dest.estimated_speed_mbps.unwrap_or(400.0)),
+                    ..Default::default()
                 }
             }
             (DeviceType::HDD, DeviceType::SataSSD) => {
@@ -63,7 +69,29 @@ impl CopyOptimizer {
                     ..Default::default()
                 }
             }
-            _ => CopyStrategy::default(),
+            _ => match (source.estimated_speed_mbps, dest.estimated_speed_mbps) {
+                (Some(source_speed), Some(dest_speed)) => CopyStrategy {
+                    buffer_size: Self::buffer_size_for(source_speed.min(dest_speed)),
+                    ..Default::default()
+                },
+                _ => CopyStrategy::default(),
+            },
+        }
+    }
+    
+    /// Velocidad del extremo más lento, medida o estimada
+    fn slowest_speed(source: &DeviceInfo, dest: &DeviceInfo) -> f64 {
+        source.estimated_speed_mbps
+            .and_then(|s1| dest.estimated_speed_mbps.map(|s2| s1.min(s2)))
+            .unwrap_or(100.0)
+    }
+    
+    fn buffer_size_for(speed_mbps: f64) -> usize {
+        match speed_mbps {
+            s if s < 50.0 => 16 * 1024,
+            s if s < 200.0 => 32 * 1024,
+            s if s < 1000.0 => 64 * 1024,
+            _ => 128 * 1024,
         }
     }
     
@@ -75,16 +103,7 @@ impl CopyOptimizer {
     ) -> CopyOptions {
         let mut optimized = base_options.clone();
         
-        let slowest_speed = source.estimated_speed_mbps
-            .and_then(|s1| dest.estimated_speed_mbps.map(|s2| s1.min(s2)))
-            .unwrap_or(100.0);
-        
-        optimized.buffer_size = match slowest_speed {
-            s if s < 50.0 => 16 * 1024,
-            s if s < 200.0 => 32 * 1024,
-            s if s < 1000.0 => 64 * 1024,
-            _ => 128 * 1024,
-        };
+        optimized.buffer_size = Self::buffer_size_for(Self::slowest_speed(source, dest));
         
         match (&source.device_type, &dest.device_type) {
             (DeviceType::NVMeSSD, DeviceType::NVMeSSD) => {
@@ -102,4 +121,280 @@ impl CopyOptimizer {
         
         optimized
     }
-}
\ No newline at end of file
+    
+    /// Trasladar una estrategia a las opciones del motor de copia.
+    /// `sync_io` en las opciones base sigue forzando O_SYNC.
+    pub fn apply_strategy(&self, strategy: &CopyStrategy, base_options: &CopyOptions) -> CopyOptions {
+        let mut options = base_options.clone();
+        
+        options.buffer_size = strategy.buffer_size;
+        options.max_threads = strategy.max_threads;
+        options.direct_io = base_options.direct_io || strategy.use_direct_io;
+        options.throttle_mbps = match (base_options.throttle_mbps, strategy.throttle_mbps) {
+            (Some(base), Some(strategy)) => Some(base.min(strategy)),
+            (base, strategy) => base.or(strategy),
+        };
+        if strategy.sync_mode != SyncMode::None {
+            options.sync_mode = strategy.sync_mode;
+        }
+        
+        options
+    }
+}
+
+/// Cada cuánto evalúa el controlador el rendimiento observado
+const TUNING_WINDOW: std::time::Duration = std::time::Duration::from_millis(500);
+
+/// Escrituras mínimas para que una ventana cuente; con menos pesa más abrir archivos
+const MIN_WINDOW_WRITES: u32 = 4;
+
+/// Mejora mínima para quedarse con un cambio
+const IMPROVEMENT_RATIO: f64 = 1.05;
+
+/// Por debajo de esta fracción del mejor rendimiento el destino se ha atascado,
+/// p. ej. al llenarse la caché de escritura de un USB
+const STALL_RATIO: f64 = 0.5;
+
+/// Una sola escritura así de lenta también es un atasco
+const STALL_LATENCY: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// Ventanas sin probar cambios después de retroceder
+const BACKOFF_COOLDOWN: u32 = 4;
+
+const MIN_TUNED_BUFFER: usize = 16 * 1024;
+const MAX_TUNED_BUFFER: usize = 8 * 1024 * 1024;
+const MAX_TUNED_THREADS: usize = 16;
+
+/// Parámetros que ha elegido el controlador adaptativo, para `CopyStats`
+#[derive(Debug, Clone, Default)]
+pub struct TuningReport {
+    pub buffer_size: usize,
+    pub threads: usize,
+    pub throughput_mbps: f64, // Última ventana evaluada
+    pub adjustments: u32,     // Cambios que se han quedado
+    pub backoffs: u32,        // Retrocesos por atasco del destino
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TunedParameter {
+    BufferSize,
+    Threads,
+}
+
+/// Cambio a prueba: se conserva si la siguiente ventana rinde más
+#[derive(Debug, Clone, Copy)]
+struct Probe {
+    parameter: TunedParameter,
+    previous: usize,
+    baseline_mbps: f64,
+}
+
+#[derive(Debug)]
+struct TunerState {
+    window_start: std::time::Instant,
+    last_write: std::time::Instant,
+    window_bytes: u64,
+    window_writes: u32,
+    best_mbps: f64,
+    probe: Option<Probe>,
+    next_parameter: TunedParameter,
+    // Parámetros que ya no mejoran al subir; se vuelven a probar tras un atasco
+    settled: Vec<TunedParameter>,
+    cooldown: u32,
+    report: TuningReport,
+}
+
+/// Controlador que ajusta el tamaño de buffer y el número de hilos mientras
+/// se copia. Parte de la estrategia estática, sube un parámetro cada vez
+/// mientras el rendimiento mejore y retrocede a la mitad si el destino se atasca.
+#[derive(Debug)]
+pub struct AdaptiveTuner {
+    buffer_size: std::sync::atomic::AtomicUsize,
+    threads: std::sync::atomic::AtomicUsize,
+    max_threads: usize,
+    state: std::sync::Mutex<TunerState>,
+}
+
+impl AdaptiveTuner {
+    pub fn new(buffer_size: usize, threads: usize) -> Self {
+        let buffer_size = buffer_size.clamp(MIN_TUNED_BUFFER, MAX_TUNED_BUFFER);
+        // Los hilos de la estrategia son el tope: el ajuste los reduce ante un atasco
+        // y los recupera después, pero nunca pasa de ahí
+        let threads = threads.clamp(1, MAX_TUNED_THREADS);
+        let now = std::time::Instant::now();
+
+        Self {
+            buffer_size: std::sync::atomic::AtomicUsize::new(buffer_size),
+            threads: std::sync::atomic::AtomicUsize::new(threads),
+            max_threads: threads,
+            state: std::sync::Mutex::new(TunerState {
+                window_start: now,
+                last_write: now,
+                window_bytes: 0,
+                window_writes: 0,
+                best_mbps: 0.0,
+                probe: None,
+                next_parameter: TunedParameter::BufferSize,
+                settled: Vec::new(),
+                cooldown: 0,
+                report: TuningReport { buffer_size, threads, ..Default::default() },
+            }),
+        }
+    }
+
+    pub fn buffer_size(&self) -> usize {
+        self.buffer_size.load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    pub fn threads(&self) -> usize {
+        self.threads.load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    /// Hilos que pueden llegar a estar activos
+    pub fn max_threads(&self) -> usize {
+        self.max_threads
+    }
+
+    pub fn report(&self) -> TuningReport {
+        self.lock().report.clone()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, TunerState> {
+        self.state.lock().unwrap_or_else(|p| p.into_inner())
+    }
+
+    /// Registrar una escritura de `bytes` que tardó `latency`
+    pub fn record(&self, bytes: u64, latency: std::time::Duration) {
+        let mut state = self.lock();
+        let now = std::time::Instant::now();
+
+        if latency >= STALL_LATENCY {
+            self.back_off(&mut state, None);
+            Self::restart_window(&mut state, now);
+            return;
+        }
+
+        // Tras una pausa o un hueco entre archivos la ventana no es representativa
+        if now.duration_since(state.last_write) > TUNING_WINDOW {
+            Self::restart_window(&mut state, now);
+        }
+        state.last_write = now;
+        state.window_bytes += bytes;
+        state.window_writes += 1;
+
+        let elapsed = now.duration_since(state.window_start);
+        if elapsed < TUNING_WINDOW {
+            return;
+        }
+
+        if state.window_writes >= MIN_WINDOW_WRITES {
+            let mbps = state.window_bytes as f64 / 1_000_000.0 / elapsed.as_secs_f64();
+            self.evaluate(&mut state, mbps);
+        }
+        Self::restart_window(&mut state, now);
+    }
+
+    fn restart_window(state: &mut TunerState, now: std::time::Instant) {
+        state.window_start = now;
+        state.last_write = now;
+        state.window_bytes = 0;
+        state.window_writes = 0;
+    }
+
+    fn evaluate(&self, state: &mut TunerState, mbps: f64) {
+        state.report.throughput_mbps = mbps;
+
+        if state.best_mbps > 0.0 && mbps < state.best_mbps * STALL_RATIO {
+            self.back_off(state, Some(mbps));
+            return;
+        }
+        state.best_mbps = state.best_mbps.max(mbps);
+
+        if let Some(probe) = state.probe.take() {
+            if mbps >= probe.baseline_mbps * IMPROVEMENT_RATIO {
+                state.report.adjustments += 1;
+            } else {
+                // No compensa: volver al valor anterior y dejar de subir este parámetro
+                self.set(probe.parameter, probe.previous);
+                state.settled.push(probe.parameter);
+            }
+        }
+        state.report.buffer_size = self.buffer_size();
+        state.report.threads = self.threads();
+
+        if state.cooldown > 0 {
+            state.cooldown -= 1;
+            return;
+        }
+
+        self.start_probe(state, mbps);
+    }
+
+    /// Subir el siguiente parámetro que aún no se haya estabilizado
+    fn start_probe(&self, state: &mut TunerState, baseline_mbps: f64) {
+        for _ in 0..2 {
+            let parameter = state.next_parameter;
+            state.next_parameter = match parameter {
+                TunedParameter::BufferSize => TunedParameter::Threads,
+                TunedParameter::Threads => TunedParameter::BufferSize,
+            };
+
+            if state.settled.contains(&parameter) {
+                continue;
+            }
+
+            let previous = self.get(parameter);
+            let next = match parameter {
+                TunedParameter::BufferSize => (previous * 2).min(MAX_TUNED_BUFFER),
+                TunedParameter::Threads => (previous + 1).min(self.max_threads),
+            };
+
+            if next == previous {
+                state.settled.push(parameter);
+                continue;
+            }
+
+            self.set(parameter, next);
+            state.probe = Some(Probe { parameter, previous, baseline_mbps });
+            break;
+        }
+
+        state.report.buffer_size = self.buffer_size();
+        state.report.threads = self.threads();
+    }
+
+    /// Reducir a la mitad buffer e hilos y volver a subir desde ahí más tarde
+    fn back_off(&self, state: &mut TunerState, mbps: Option<f64>) {
+        log::debug!(
+            "Destino atascado ({:?} MB/s, mejor {:.1}); reduciendo buffer e hilos",
+            mbps,
+            state.best_mbps,
+        );
+
+        self.set(TunedParameter::BufferSize, (self.buffer_size() / 2).max(MIN_TUNED_BUFFER));
+        self.set(TunedParameter::Threads, (self.threads() / 2).max(1));
+
+        state.probe = None;
+        state.settled.clear();
+        state.best_mbps = mbps.unwrap_or(0.0);
+        state.cooldown = BACKOFF_COOLDOWN;
+        state.report.backoffs += 1;
+        state.report.buffer_size = self.buffer_size();
+        state.report.threads = self.threads();
+    }
+
+    fn get(&self, parameter: TunedParameter) -> usize {
+        match parameter {
+            TunedParameter::BufferSize => self.buffer_size(),
+            TunedParameter::Threads => self.threads(),
+        }
+    }
+
+    fn set(&self, parameter: TunedParameter, value: usize) {
+        let target = match parameter {
+            TunedParameter::BufferSize => &self.buffer_size,
+            TunedParameter::Threads => &self.threads,
+        };
+        target.store(value, std::sync::atomic::Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,836 @@
+// src/core/parallel_copy.rs
+use std::fs::{File, OpenOptions};
+use std::os::unix::fs::{FileExt, OpenOptionsExt};
+use std::os::unix::io::AsRawFd;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use nix::errno::Errno;
+use nix::unistd::{lseek, Whence};
+use xxhash_rust::xxh3::{xxh3_128, Xxh3};
+
+use crate::core::control::TransferControl;
+use crate::core::copy_engine::{CopyMethod, CopyStats, SyncMode, VerificationMode};
+use crate::core::direct_io::{align_up, logical_block_size, open_direct, sync_flags, AlignedBuffer};
+use crate::core::error_recovery::{CopyError, IoOperation, IoResultExt};
+use crate::core::kernel_copy::{try_copy_file_range, try_reflink, KernelCopyOutcome};
+use crate::core::optimizer::AdaptiveTuner;
+use crate::core::throttle::Throttle;
+
+/// Por debajo de este tamaño no merece la pena repartir el archivo entre hilos
+const PARALLEL_THRESHOLD: u64 = 8 * 1024 * 1024;
+
+/// Tamaño mínimo de cada rango asignado a un hilo
+const MIN_RANGE_SIZE: u64 = 1024 * 1024;
+
+/// Rangos por hilo, para que los hilos rápidos no esperen a los lentos
+const RANGES_PER_THREAD: u64 = 4;
+
+/// Alineación de los buffers cuando no se usa O_DIRECT
+const DEFAULT_ALIGNMENT: usize = 4096;
+
+/// Cada cuánto mira un hilo sobrante si el ajuste adaptativo le deja trabajar
+const IDLE_WORKER_POLL: Duration = Duration::from_millis(20);
+
+#[derive(Debug, Clone, Copy)]
+pub struct ChunkRange {
+    pub offset: u64,
+    pub len: u64,
+}
+
+/// Resultado de copiar un archivo por chunks
+#[derive(Debug, Clone, Copy)]
+pub struct ChunkCopyResult {
+    pub bytes: u64,
+    /// Hash xxh3 del origen si la verificación estaba activa
+    pub digest: Option<u128>,
+    pub method: CopyMethod,
+}
+
+/// Copia un archivo repartiendo rangos entre un pool de hilos que usan
+/// lecturas y escrituras posicionales (pread/pwrite). Si se permite, antes
+/// prueba a clonar el archivo o a copiarlo dentro del kernel.
+pub struct ParallelChunkCopier {
+    buffer_size: usize,
+    max_threads: usize,
+    verification: Option<VerificationMode>,
+    sparse: bool,
+    kernel_acceleration: bool,
+    direct_alignment: Option<usize>,
+    sync_mode: SyncMode,
+    throttle: Throttle,
+    control: Option<Arc<TransferControl>>,
+    tuner: Option<Arc<AdaptiveTuner>>,
+}
+
+/// Descriptores que usa cada hilo para copiar un rango
+struct RangeFiles<'a> {
+    src: &'a File,
+    dst: &'a File,
+    src_path: &'a Path,
+    dst_path: &'a Path,
+    /// Descriptor con caché para releer el destino al verificar
+    verify: &'a File,
+    /// Alineación exigida si `src`/`dst` están abiertos con O_DIRECT
+    direct_alignment: Option<u64>,
+}
+
+impl ParallelChunkCopier {
+    pub fn new(buffer_size: usize, max_threads: usize) -> Self {
+        Self {
+            buffer_size: buffer_size.max(4096),
+            max_threads: max_threads.max(1),
+            verification: None,
+            sparse: false,
+            kernel_acceleration: false,
+            direct_alignment: None,
+            sync_mode: SyncMode::None,
+            throttle: Throttle::default(),
+            control: None,
+            tuner: None,
+        }
+    }
+
+    /// Activar la verificación xxh3 del destino
+    pub fn with_verification(mut self, mode: Option<VerificationMode>) -> Self {
+        self.verification = mode;
+        self
+    }
+
+    /// Activar la detección de huecos en archivos sparse
+    pub fn with_sparse(mut self, sparse: bool) -> Self {
+        self.sparse = sparse;
+        self
+    }
+
+    /// Intentar reflink y copy_file_range antes de copiar por chunks
+    pub fn with_kernel_acceleration(mut self, enabled: bool) -> Self {
+        self.kernel_acceleration = enabled;
+        self
+    }
+
+    /// Usar O_DIRECT con buffers alineados a `alignment` (tamaño de bloque del dispositivo)
+    pub fn with_direct_io(mut self, alignment: Option<usize>) -> Self {
+        self.direct_alignment = alignment;
+        self
+    }
+
+    /// Abrir el destino con O_SYNC u O_DSYNC
+    pub fn with_sync_mode(mut self, sync_mode: SyncMode) -> Self {
+        self.sync_mode = sync_mode;
+        self
+    }
+
+    /// Limitar el ancho de banda de la copia
+    pub fn with_throttle(mut self, throttle: Throttle) -> Self {
+        self.throttle = throttle;
+        self
+    }
+
+    /// Atender pausas y cancelaciones entre chunks
+    pub fn with_control(mut self, control: Arc<TransferControl>) -> Self {
+        self.control = Some(control);
+        self
+    }
+
+    /// Dejar que `tuner` decida el tamaño de buffer y los hilos activos sobre la marcha.
+    /// Los archivos que solo admiten un rango siguen copiándose con un hilo.
+    pub fn with_tuner(mut self, tuner: Option<Arc<AdaptiveTuner>>) -> Self {
+        self.tuner = tuner;
+        self
+    }
+
+    /// Hilos para los que se reparten rangos
+    fn planned_threads(&self) -> usize {
+        match &self.tuner {
+            Some(tuner) if self.max_threads > 1 => tuner.max_threads(),
+            _ => self.max_threads,
+        }
+    }
+
+    /// Tamaño de cada lectura y escritura ahora mismo
+    fn current_buffer_size(&self) -> usize {
+        match &self.tuner {
+            Some(tuner) => tuner.buffer_size(),
+            None => self.buffer_size,
+        }
+    }
+
+    /// Bytes por lectura en un rango de `len`: el buffer actual sin pasar del rango.
+    /// Múltiplo de la alineación, por si se usa O_DIRECT
+    fn chunk_size(&self, len: u64, alignment: usize) -> usize {
+        let size = self.current_buffer_size().min(align_up(len, alignment as u64) as usize);
+        (size / alignment * alignment).max(alignment)
+    }
+
+    /// Con ajuste adaptativo, si este hilo sobra de momento
+    fn is_idle_worker(&self, worker: usize) -> bool {
+        match &self.tuner {
+            Some(tuner) => worker >= tuner.threads(),
+            None => false,
+        }
+    }
+
+    fn checkpoint(&self) -> Result<(), CopyError> {
+        match &self.control {
+            Some(control) => control.checkpoint(),
+            None => Ok(()),
+        }
+    }
+
+    /// Localizar las zonas con datos del archivo mediante SEEK_DATA/SEEK_HOLE.
+    /// Si el sistema de archivos no lo soporta, todo el archivo cuenta como datos.
+    pub fn data_extents(file: &File, len: u64) -> Vec<ChunkRange> {
+        let fd = file.as_raw_fd();
+        let whole_file = vec![ChunkRange { offset: 0, len }];
+        let mut extents = Vec::new();
+        let mut offset: i64 = 0;
+
+        while (offset as u64) < len {
+            let start = match lseek(fd, offset, Whence::SeekData) {
+                Ok(start) => start,
+                // No quedan datos: el resto del archivo es un hueco
+                Err(Errno::ENXIO) => break,
+                Err(_) => return whole_file,
+            };
+
+            let end = match lseek(fd, start, Whence::SeekHole) {
+                Ok(end) => end.min(len as i64),
+                Err(_) => return whole_file,
+            };
+
+            if end <= start {
+                break;
+            }
+
+            extents.push(ChunkRange { offset: start as u64, len: (end - start) as u64 });
+            offset = end;
+        }
+
+        extents
+    }
+
+    /// Dividir las zonas con datos en rangos alineados al tamaño de buffer
+    pub fn plan_ranges(&self, extents: &[ChunkRange]) -> Vec<ChunkRange> {
+        let data_len: u64 = extents.iter().map(|extent| extent.len).sum();
+
+        if data_len <= PARALLEL_THRESHOLD || self.max_threads == 1 {
+            return extents.to_vec();
+        }
+
+        let buffer = self.buffer_size as u64;
+        let target = data_len / (self.planned_threads() as u64 * RANGES_PER_THREAD);
+        let range_size = target.max(MIN_RANGE_SIZE).div_ceil(buffer) * buffer;
+
+        let mut ranges = Vec::new();
+        for extent in extents {
+            let end = extent.offset + extent.len;
+            let mut offset = extent.offset;
+            while offset < end {
+                let range_len = range_size.min(end - offset);
+                ranges.push(ChunkRange { offset, len: range_len });
+                offset += range_len;
+            }
+        }
+
+        ranges
+    }
+
+    /// Copiar `source` en `dest` actualizando `stats` a medida que se escriben bytes.
+    pub fn copy(
+        &self,
+        source: &Path,
+        dest: &Path,
+        stats: &Arc<Mutex<CopyStats>>,
+    ) -> Result<ChunkCopyResult, CopyError> {
+        self.copy_resumable(source, dest, stats, 0, |_| {})
+    }
+
+    /// Como `copy`, pero continuando desde `resume_from` si el destino ya tiene esos bytes.
+    /// Lo escrito antes se compara con el origen rango a rango y solo se salta lo que coincide.
+    /// `on_checkpoint` recibe el offset hasta el que el destino está escrito sin huecos pendientes.
+    pub fn copy_resumable(
+        &self,
+        source: &Path,
+        dest: &Path,
+        stats: &Arc<Mutex<CopyStats>>,
+        resume_from: u64,
+        on_checkpoint: impl Fn(u64) + Sync,
+    ) -> Result<ChunkCopyResult, CopyError> {
+        // Abrir un FIFO o un dispositivo para lectura puede bloquear indefinidamente
+        let metadata = std::fs::metadata(source).io_context(IoOperation::Metadata, source)?;
+        if !metadata.is_file() {
+            return Err(CopyError::SpecialFile(source.display().to_string()));
+        }
+
+        self.checkpoint()?;
+
+        let src = File::open(source).io_context(IoOperation::Open, source)?;
+        let len = src.metadata().io_context(IoOperation::Metadata, source)?.len();
+
+        // Solo se reanuda si el destino parcial sigue ahí y el origen no ha encogido
+        let dest_len = std::fs::metadata(dest).map(|m| m.len()).unwrap_or(0);
+        let resume_from = match resume_from {
+            offset if offset > 0 && offset <= len && offset <= dest_len => offset,
+            _ => 0,
+        };
+
+        let dst = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(resume_from == 0)
+            .custom_flags(sync_flags(self.sync_mode))
+            .open(dest)
+            .io_context(IoOperation::Create, dest)?;
+
+        let extents = match self.sparse {
+            true => Self::data_extents(&src, len),
+            false if len > 0 => vec![ChunkRange { offset: 0, len }],
+            false => Vec::new(),
+        };
+
+        // El progreso y la estimación cuentan solo los bytes que se escriben de verdad
+        let data_len = extents.iter().map(|extent| extent.len).sum::<u64>();
+        let hole_bytes = len - data_len;
+        if hole_bytes > 0 {
+            let mut stats = stats.lock().unwrap_or_else(|p| p.into_inner());
+            stats.total_bytes = stats.total_bytes.saturating_sub(hole_bytes);
+        }
+
+        let ranges = Self::split_at(self.plan_ranges(&extents), resume_from);
+
+        // Rangos ya presentes en el destino y comprobados
+        let resumed = match resume_from {
+            0 => Vec::new(),
+            _ => self.verified_prefix((&src, source), (&dst, dest), &ranges, resume_from)?,
+        };
+        let resumed_until = resumed.len().checked_sub(1).map(|last| ranges[last].offset + ranges[last].len).unwrap_or(0);
+        if resumed_until > 0 {
+            log::info!("Reanudando {} desde el byte {}", dest.display(), resumed_until);
+            let resumed_bytes = ranges[..resumed.len()].iter().map(|range| range.len).sum::<u64>();
+            let mut stats = stats.lock().unwrap_or_else(|p| p.into_inner());
+            stats.total_bytes = stats.total_bytes.saturating_sub(resumed_bytes);
+        }
+
+        let record = |n: u64| {
+            if let Ok(mut stats) = stats.lock() {
+                stats.record_bytes(n);
+            }
+        };
+
+        // Las rutas del kernel no pasan los datos por aquí, así que no sirven
+        // para hashear mientras se copia
+        let accelerate = self.kernel_acceleration && self.verification != Some(VerificationMode::Streaming);
+
+        // Un reflink sustituiría lo ya copiado, así que solo se intenta al empezar de cero
+        if accelerate && resumed.is_empty() {
+            if let KernelCopyOutcome::Done = try_reflink(&src, &dst).io_context(IoOperation::Copy, dest)? {
+                record(data_len);
+                return self.finish_kernel_copy((&src, source), (&dst, dest), len, &ranges, CopyMethod::Reflink);
+            }
+        }
+
+        // Reservar el tamaño final para que cada hilo pueda escribir en su offset.
+        // Lo que no se escriba después queda como hueco.
+        dst.set_len(len).io_context(IoOperation::Truncate, dest)?;
+
+        // Con O_DIRECT pedido, la copia debe pasar por los buffers alineados
+        if accelerate && self.direct_alignment.is_none() {
+            let throttled = |n: u64, copied_until: u64| {
+                self.throttle.acquire(n);
+                record(n);
+                // Los rangos van en orden: todo lo anterior ya está en el destino
+                on_checkpoint(copied_until);
+                // ECANCELED solo sale de aquí; se traduce de vuelta abajo
+                self.checkpoint().map_err(|_| Errno::ECANCELED)
+            };
+            let pending = Self::split_at(extents.clone(), resumed_until)
+                .into_iter()
+                .filter(|extent| extent.offset >= resumed_until)
+                .collect::<Vec<_>>();
+            let outcome = try_copy_file_range(&src, &dst, &pending, throttled).map_err(|e| match e {
+                Errno::ECANCELED => CopyError::Cancelled,
+                e => CopyError::from_io(e.into(), IoOperation::Copy, dest),
+            })?;
+            if let KernelCopyOutcome::Done = outcome {
+                return self.finish_kernel_copy((&src, source), (&dst, dest), len, &ranges, CopyMethod::CopyFileRange);
+            }
+        }
+        let workers = self.planned_threads().min(ranges.len() - resumed.len()).max(1);
+        let next_range = AtomicUsize::new(resumed.len());
+        let abort = AtomicBool::new(false);
+        let first_error: Mutex<Option<CopyError>> = Mutex::new(None);
+        let mut digests = resumed.clone();
+        digests.resize(ranges.len(), 0);
+        let digests: Mutex<Vec<u128>> = Mutex::new(digests);
+
+        // Rangos terminados y cuántos seguidos desde el principio
+        let mut done = vec![false; ranges.len()];
+        done[..resumed.len()].fill(true);
+        let progress: Mutex<(Vec<bool>, usize)> = Mutex::new((done, resumed.len()));
+        let complete_range = |index: usize| {
+            let mut progress = progress.lock().unwrap_or_else(|p| p.into_inner());
+            let (done, prefix) = &mut *progress;
+            done[index] = true;
+
+            let before = *prefix;
+            while *prefix < done.len() && done[*prefix] {
+                *prefix += 1;
+            }
+            if *prefix > before {
+                let last = ranges[*prefix - 1];
+                on_checkpoint(last.offset + last.len);
+            }
+        };
+
+        // O_DIRECT exige offsets alineados al bloque lógico de los dos discos, que
+        // pueden ser distintos; si algún rango no lo está se usa la caché
+        let direct = self.direct_alignment.and_then(|configured| {
+            let (direct_src, direct_dst) = open_direct(source, dest, self.sync_mode)?;
+            let alignment = [logical_block_size(&direct_src), logical_block_size(&direct_dst)]
+                .into_iter()
+                .flatten()
+                .fold(configured, usize::max);
+            ranges
+                .iter()
+                .all(|range| range.offset % alignment as u64 == 0)
+                .then_some((direct_src, direct_dst, alignment))
+        });
+
+        let files = RangeFiles {
+            src: direct.as_ref().map(|(src, _, _)| src).unwrap_or(&src),
+            dst: direct.as_ref().map(|(_, dst, _)| dst).unwrap_or(&dst),
+            src_path: source,
+            dst_path: dest,
+            verify: &dst,
+            direct_alignment: direct.as_ref().map(|(_, _, alignment)| *alignment as u64),
+        };
+        let alignment = direct.as_ref()
+            .map(|(_, _, alignment)| *alignment)
+            .or(self.direct_alignment)
+            .unwrap_or(DEFAULT_ALIGNMENT);
+        let largest_range = ranges.iter().map(|range| range.len).max().unwrap_or(0);
+
+        std::thread::scope(|scope| {
+            for worker in 0..workers {
+                let ranges = &ranges;
+                let next_range = &next_range;
+                let abort = &abort;
+                let first_error = &first_error;
+                let digests = &digests;
+                let complete_range = &complete_range;
+                let files = &files;
+
+                scope.spawn(move || {
+                    // Se agranda solo si el ajuste adaptativo sube el tamaño de buffer
+                    let mut buffer = AlignedBuffer::new(self.chunk_size(largest_range, alignment), alignment);
+                    let streaming = self.verification == Some(VerificationMode::Streaming);
+                    let mut readback = match streaming {
+                        true => vec![0u8; buffer.len()],
+                        false => Vec::new(),
+                    };
+
+                    while !abort.load(Ordering::Relaxed) {
+                        // Hilo sobrante según el ajuste adaptativo: esperar mientras quede trabajo
+                        if self.is_idle_worker(worker) {
+                            if next_range.load(Ordering::SeqCst) >= ranges.len() {
+                                break;
+                            }
+                            std::thread::sleep(IDLE_WORKER_POLL);
+                            continue;
+                        }
+
+                        let index = next_range.fetch_add(1, Ordering::SeqCst);
+                        let Some(range) = ranges.get(index) else {
+                            break;
+                        };
+
+                        let size = self.chunk_size(range.len, alignment);
+                        if buffer.len() < size {
+                            buffer = AlignedBuffer::new(size, alignment);
+                        }
+                        if streaming && readback.len() < size {
+                            readback.resize(size, 0);
+                        }
+                        let readback_len = readback.len().min(size);
+                        let result = self.copy_range(files, *range, &mut buffer[..size], &mut readback[..readback_len], abort, record);
+
+                        let result = result.map(|digest| {
+                            if let Some(digest) = digest {
+                                digests.lock().unwrap_or_else(|p| p.into_inner())[index] = digest;
+                            }
+                            complete_range(index);
+                        });
+
+                        if let Err(e) = result {
+                            abort.store(true, Ordering::SeqCst);
+                            let mut slot = first_error.lock().unwrap_or_else(|p| p.into_inner());
+                            slot.get_or_insert(e);
+                            break;
+                        }
+                    }
+                });
+            }
+        });
+
+        if let Some(e) = first_error.into_inner().unwrap_or_else(|p| p.into_inner()) {
+            return Err(e);
+        }
+
+        if files.direct_alignment.is_some() {
+            // Quitar el relleno de la última escritura alineada
+            dst.set_len(len).io_context(IoOperation::Truncate, dest)?;
+        }
+
+        let digests = digests.into_inner().unwrap_or_else(|p| p.into_inner());
+
+        if let Some(VerificationMode::ReadBack) = self.verification {
+            self.verify_from_disk((&dst, dest), &ranges, &digests)?;
+        }
+
+        Ok(ChunkCopyResult {
+            bytes: len,
+            digest: self.verification.map(|_| Self::combine_digests(len, &digests)),
+            method: CopyMethod::Chunked,
+        })
+    }
+
+    /// Partir el rango que contiene `offset` para que el punto de reanudación sea un límite
+    fn split_at(ranges: Vec<ChunkRange>, offset: u64) -> Vec<ChunkRange> {
+        ranges
+            .into_iter()
+            .flat_map(|range| {
+                let end = range.offset + range.len;
+                if range.offset < offset && offset < end {
+                    vec![
+                        ChunkRange { offset: range.offset, len: offset - range.offset },
+                        ChunkRange { offset, len: end - offset },
+                    ]
+                } else {
+                    vec![range]
+                }
+            })
+            .collect()
+    }
+
+    /// Hashes de los rangos anteriores a `resume_from` que coinciden en origen y destino.
+    /// Se para en el primero distinto: lo escrito justo antes de un corte puede no haber llegado a disco.
+    fn verified_prefix(
+        &self,
+        src: (&File, &Path),
+        dst: (&File, &Path),
+        ranges: &[ChunkRange],
+        resume_from: u64,
+    ) -> Result<Vec<u128>, CopyError> {
+        let mut digests = Vec::new();
+
+        for range in ranges.iter().take_while(|range| range.offset + range.len <= resume_from) {
+            let expected = self.hash_ranges(src.0, src.1, std::slice::from_ref(range))?;
+            match self.hash_ranges(dst.0, dst.1, std::slice::from_ref(range)) {
+                Ok(actual) if actual == expected => digests.push(expected[0]),
+                _ => break,
+            }
+        }
+
+        Ok(digests)
+    }
+
+    /// Tras una copia hecha por el kernel solo queda la verificación por relectura
+    fn finish_kernel_copy(
+        &self,
+        src: (&File, &Path),
+        dst: (&File, &Path),
+        len: u64,
+        ranges: &[ChunkRange],
+        method: CopyMethod,
+    ) -> Result<ChunkCopyResult, CopyError> {
+        let digest = match self.verification {
+            Some(_) => {
+                let digests = self.hash_ranges(src.0, src.1, ranges)?;
+                self.verify_from_disk(dst, ranges, &digests)?;
+                Some(Self::combine_digests(len, &digests))
+            }
+            None => None,
+        };
+
+        Ok(ChunkCopyResult { bytes: len, digest, method })
+    }
+
+    /// Hash del archivo completo a partir de los hashes de cada rango.
+    /// Con un único rango que cubre todo el archivo coincide con el xxh3-128 del contenido.
+    fn combine_digests(len: u64, digests: &[u128]) -> u128 {
+        match digests {
+            [] => xxh3_128(&[]),
+            [single] => *single,
+            _ => {
+                let mut hasher = Xxh3::new();
+                hasher.update(&len.to_le_bytes());
+                for digest in digests {
+                    hasher.update(&digest.to_le_bytes());
+                }
+                hasher.digest128()
+            }
+        }
+    }
+
+    /// Forzar el volcado a disco, descartar la caché y releer el destino
+    fn verify_from_disk(&self, dst: (&File, &Path), ranges: &[ChunkRange], digests: &[u128]) -> Result<(), CopyError> {
+        let (dst, path) = dst;
+        dst.sync_all().io_context(IoOperation::Sync, path)?;
+
+        // Sin esto la relectura vendría de la caché de páginas y no del dispositivo
+        let _ = nix::fcntl::posix_fadvise(
+            dst.as_raw_fd(),
+            0,
+            0,
+            nix::fcntl::PosixFadviseAdvice::POSIX_FADV_DONTNEED,
+        );
+
+        if self.hash_ranges(dst, path, ranges)? != digests {
+            return Err(CopyError::HashMismatch);
+        }
+
+        Ok(())
+    }
+
+    /// Hash xxh3 de cada rango de un archivo
+    fn hash_ranges(&self, file: &File, path: &Path, ranges: &[ChunkRange]) -> Result<Vec<u128>, CopyError> {
+        let mut buffer = vec![0u8; self.buffer_size];
+        let mut digests = Vec::with_capacity(ranges.len());
+
+        for range in ranges {
+            let mut hasher = Xxh3::new();
+            let end = range.offset + range.len;
+            let mut offset = range.offset;
+
+            while offset < end {
+                let want = (end - offset).min(buffer.len() as u64) as usize;
+                let read = match file.read_at(&mut buffer[..want], offset) {
+                    Ok(0) => return Err(CopyError::HashMismatch),
+                    Ok(n) => n,
+                    Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                    Err(e) => return Err(CopyError::from_io(e, IoOperation::Read, path)),
+                };
+                hasher.update(&buffer[..read]);
+                offset += read as u64;
+            }
+
+            digests.push(hasher.digest128());
+        }
+
+        Ok(digests)
+    }
+
+    /// Copiar un rango concreto con pread/pwrite, notificando cada bloque escrito.
+    /// Si la verificación está activa devuelve el hash del origen para ese rango.
+    fn copy_range(
+        &self,
+        files: &RangeFiles,
+        range: ChunkRange,
+        buffer: &mut [u8],
+        readback: &mut [u8],
+        abort: &AtomicBool,
+        mut on_progress: impl FnMut(u64),
+    ) -> Result<Option<u128>, CopyError> {
+        let end = range.offset + range.len;
+        let mut offset = range.offset;
+        let mut src_hasher = self.verification.map(|_| Xxh3::new());
+        let mut dst_hasher = match self.verification {
+            Some(VerificationMode::Streaming) => Some(Xxh3::new()),
+            _ => None,
+        };
+
+        while offset < end {
+            // Otro hilo ya falló; el rango queda sin terminar y su error es el que cuenta
+            if abort.load(Ordering::Relaxed) {
+                return Err(CopyError::Cancelled);
+            }
+            // En pausa el hilo espera aquí con los descriptores abiertos
+            self.checkpoint()?;
+
+            let want = (end - offset).min(buffer.len() as u64) as usize;
+
+            // Con O_DIRECT las longitudes también deben ir alineadas
+            let read_len = match files.direct_alignment {
+                Some(alignment) => (align_up(want as u64, alignment) as usize).min(buffer.len()),
+                None => want,
+            };
+
+            let read = match files.src.read_at(&mut buffer[..read_len], offset) {
+                Ok(0) => {
+                    return Err(CopyError::Io(format!(
+                        "{}: el origen se truncó durante la copia (offset {})",
+                        files.src_path.display(),
+                        offset
+                    )))
+                }
+                Ok(n) => n,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(CopyError::from_io(e, IoOperation::Read, files.src_path)),
+            };
+            let data = read.min(want);
+            self.throttle.acquire(data as u64);
+
+            // La cola sin alinear se rellena con ceros; el archivo se recorta al final
+            let write_len = match files.direct_alignment {
+                Some(alignment) => {
+                    let padded = align_up(data as u64, alignment) as usize;
+                    if read < padded {
+                        buffer[read..padded].fill(0);
+                    }
+                    padded
+                }
+                None => data,
+            };
+
+            let write_started = Instant::now();
+            files.dst.write_all_at(&buffer[..write_len], offset)
+                .io_context(IoOperation::Write, files.dst_path)?;
+            if let Some(tuner) = &self.tuner {
+                tuner.record(data as u64, write_started.elapsed());
+            }
+
+            if let Some(hasher) = src_hasher.as_mut() {
+                hasher.update(&buffer[..data]);
+            }
+
+            // Releer lo que acabamos de escribir para hashear el destino real
+            if let Some(hasher) = dst_hasher.as_mut() {
+                files.verify.read_exact_at(&mut readback[..data], offset)
+                    .io_context(IoOperation::Read, files.dst_path)?;
+                hasher.update(&readback[..data]);
+            }
+
+            offset += data as u64;
+            on_progress(data as u64);
+        }
+
+        if let (Some(src_hasher), Some(dst_hasher)) = (&src_hasher, &dst_hasher) {
+            if src_hasher.digest128() != dst_hasher.digest128() {
+                return Err(CopyError::HashMismatch);
+            }
+        }
+
+        Ok(src_hasher.map(|h| h.digest128()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::test_support::ScratchDir;
+
+    fn bounds(ranges: &[ChunkRange]) -> Vec<(u64, u64)> {
+        ranges.iter().map(|range| (range.offset, range.len)).collect()
+    }
+
+    #[test]
+    fn small_files_keep_their_extents() {
+        let copier = ParallelChunkCopier::new(1024 * 1024, 4);
+        let extents = [ChunkRange { offset: 0, len: 4096 }, ChunkRange { offset: 65536, len: 100 }];
+
+        assert_eq!(bounds(&copier.plan_ranges(&extents)), bounds(&extents));
+    }
+
+    #[test]
+    fn large_files_are_split_in_buffer_multiples() {
+        let buffer = 256 * 1024;
+        let copier = ParallelChunkCopier::new(buffer, 4);
+        let len = 64 * 1024 * 1024 + 123;
+        let ranges = copier.plan_ranges(&[ChunkRange { offset: 0, len }]);
+
+        assert!(ranges.len() > 1);
+        // Contiguos, sin solapes y cubriendo todo el archivo
+        let mut expected = 0;
+        for range in &ranges {
+            assert_eq!(range.offset, expected);
+            expected += range.len;
+        }
+        assert_eq!(expected, len);
+        // Todos menos el último son múltiplos del buffer
+        for range in &ranges[..ranges.len() - 1] {
+            assert_eq!(range.len % buffer as u64, 0);
+            assert!(range.len >= MIN_RANGE_SIZE);
+        }
+    }
+
+    #[test]
+    fn split_at_cuts_only_the_range_that_contains_the_offset() {
+        let ranges = vec![ChunkRange { offset: 0, len: 100 }, ChunkRange { offset: 100, len: 100 }];
+
+        assert_eq!(bounds(&ParallelChunkCopier::split_at(ranges.clone(), 150)), [(0, 100), (100, 50), (150, 50)]);
+        assert_eq!(bounds(&ParallelChunkCopier::split_at(ranges, 100)), [(0, 100), (100, 100)]);
+    }
+
+    #[test]
+    fn buffers_do_not_outgrow_the_range() {
+        let copier = ParallelChunkCopier::new(1024 * 1024, 4);
+
+        assert_eq!(copier.chunk_size(10_000, 4096), 12288);
+        assert_eq!(copier.chunk_size(64 * 1024 * 1024, 4096), 1024 * 1024);
+        assert_eq!(copier.chunk_size(0, 4096), 4096);
+    }
+
+    #[test]
+    fn copies_match_the_source() {
+        let dir = ScratchDir::new("parallel-copy");
+        let data: Vec<u8> = (0..12 * 1024 * 1024u32).map(|i| (i % 251) as u8).collect();
+        std::fs::write(dir.join("source"), &data).unwrap();
+
+        let stats = Arc::new(Mutex::new(CopyStats::new()));
+        ParallelChunkCopier::new(256 * 1024, 4)
+            .copy_resumable(&dir.join("source"), &dir.join("dest"), &stats, 0, |_| {})
+            .unwrap();
+
+        assert_eq!(std::fs::read(dir.join("dest")).unwrap(), data);
+        assert_eq!(stats.lock().unwrap().bytes_transferred, data.len() as u64);
+    }
+
+    #[test]
+    fn data_extents_skip_holes() {
+        let dir = ScratchDir::new("parallel-sparse");
+        let path = dir.join("sparse");
+        let file = OpenOptions::new().read(true).write(true).create(true).truncate(true).open(&path).unwrap();
+        let len = 8 * 1024 * 1024;
+        file.set_len(len).unwrap();
+        file.write_all_at(&[1u8; 4096], 0).unwrap();
+        file.write_all_at(&[2u8; 4096], 4 * 1024 * 1024).unwrap();
+        file.sync_all().unwrap();
+
+        let extents = ParallelChunkCopier::data_extents(&file, len);
+
+        // Sin soporte de SEEK_DATA se devuelve el archivo entero
+        let data = extents.iter().map(|extent| extent.len).sum::<u64>();
+        assert!(data == len || data < len / 2, "{:?}", bounds(&extents));
+        for written in [0, 4 * 1024 * 1024] {
+            assert!(extents.iter().any(|extent| extent.offset <= written && written + 4096 <= extent.offset + extent.len));
+        }
+    }
+
+    #[test]
+    fn copy_file_range_records_checkpoints() {
+        let dir = ScratchDir::new("parallel-checkpoint");
+        let len = 20 * 1024 * 1024;
+        std::fs::write(dir.join("source"), vec![7u8; len]).unwrap();
+
+        let stats = Arc::new(Mutex::new(CopyStats::new()));
+        let checkpoints = Mutex::new(Vec::new());
+        let result = ParallelChunkCopier::new(1024 * 1024, 2)
+            .with_kernel_acceleration(true)
+            .copy_resumable(&dir.join("source"), &dir.join("dest"), &stats, 0, |offset| {
+                checkpoints.lock().unwrap().push(offset);
+            })
+            .unwrap();
+
+        let checkpoints = checkpoints.into_inner().unwrap();
+        assert_eq!(std::fs::read(dir.join("dest")).unwrap().len(), len);
+        // Un reflink termina de golpe y no necesita puntos de reanudación
+        if result.method != CopyMethod::Reflink {
+            assert!(checkpoints.windows(2).all(|pair| pair[0] <= pair[1]), "{:?}", checkpoints);
+            assert_eq!(checkpoints.last().copied(), Some(len as u64));
+        }
+    }
+}
@@ -0,0 +1,184 @@
+// src/core/preflight.rs
+// Comprobaciones antes de encolar: espacio, límites del sistema de archivos
+// de destino y nombres que no admite, para no fallar a mitad de la copia
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+
+use crate::core::device_detector::DeviceInfo;
+use crate::core::device_queue::{ItemType, TransferItem};
+
+/// Tamaño máximo de un archivo en FAT32: 4 GiB menos un byte
+pub const FAT32_MAX_FILE_SIZE: u64 = 4 * 1024 * 1024 * 1024 - 1;
+
+/// Caracteres que no admiten FAT, exFAT ni NTFS (además de los de control)
+const WINDOWS_INVALID_CHARS: &[char] = &['"', '*', '/', ':', '<', '>', '?', '\\', '|'];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PreflightProblem {
+    InsufficientSpace { required: u64, available: u64 },
+    FileTooLarge { path: PathBuf, size: u64, limit: u64 },
+    ReadOnly,
+    NotWritable(PathBuf),
+    InvalidName { path: PathBuf, reason: String },
+}
+
+impl std::fmt::Display for PreflightProblem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        const MB: u64 = 1024 * 1024;
+        match self {
+            PreflightProblem::InsufficientSpace { required, available } => write!(
+                f,
+                "No hay espacio suficiente: se necesitan {} MB y hay {} MB libres",
+                required.div_ceil(MB),
+                available / MB
+            ),
+            PreflightProblem::FileTooLarge { path, size, limit } => write!(
+                f,
+                "{} ocupa {} MB y el destino no admite archivos de más de {} MB",
+                path.display(),
+                size.div_ceil(MB),
+                limit / MB
+            ),
+            PreflightProblem::ReadOnly => write!(f, "El destino está montado en solo lectura"),
+            PreflightProblem::NotWritable(path) => write!(f, "Sin permiso de escritura en {}", path.display()),
+            PreflightProblem::InvalidName { path, reason } => write!(f, "{}: {}", path.display(), reason),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PreflightReport {
+    pub required_bytes: u64,  // Espacio que hará falta, redondeado a bloques
+    pub available_bytes: u64,
+    pub problems: Vec<PreflightProblem>,
+}
+
+impl PreflightReport {
+    pub fn is_ok(&self) -> bool {
+        self.problems.is_empty()
+    }
+
+    /// Todos los problemas en un texto, uno por línea
+    pub fn summary(&self) -> String {
+        self.problems.iter().map(|problem| problem.to_string()).collect::<Vec<_>>().join("\n")
+    }
+}
+
+/// Sistemas de archivos con las restricciones de nombres de Windows.
+/// `fuseblk` suele ser ntfs-3g o exfat-fuse.
+fn has_windows_names(filesystem: &str) -> bool {
+    matches!(filesystem, "vfat" | "msdos" | "fat" | "exfat" | "ntfs" | "ntfs3" | "fuseblk")
+}
+
+fn is_fat(filesystem: &str) -> bool {
+    matches!(filesystem, "vfat" | "msdos" | "fat")
+}
+
+/// Motivo por el que el nombre no se puede crear en el destino
+fn invalid_name_reason(name: &str, filesystem: &str) -> Option<String> {
+    if let Some(c) = name.chars().find(|c| WINDOWS_INVALID_CHARS.contains(c) || c.is_control()) {
+        return Some(format!("el carácter {:?} no está permitido en {}", c, filesystem));
+    }
+    // vfat recorta puntos y espacios finales, así que dos nombres podrían chocar
+    if is_fat(filesystem) && (name.ends_with('.') || name.ends_with(' ')) {
+        return Some(format!("{} no admite nombres que terminan en punto o espacio", filesystem));
+    }
+    None
+}
+
+fn round_up(size: u64, block_size: u64) -> u64 {
+    size.div_ceil(block_size.max(1)) * block_size.max(1)
+}
+
+/// Espacio que ocupa ya en disco un archivo que se va a sobrescribir
+fn existing_allocation(path: &Path) -> u64 {
+    std::fs::symlink_metadata(path)
+        .ok()
+        .filter(|metadata| metadata.is_file())
+        .map(|metadata| metadata.blocks() * 512)
+        .unwrap_or(0)
+}
+
+/// Primer directorio existente en el camino hacia `path`
+fn nearest_existing(path: &Path) -> Option<&Path> {
+    path.ancestors().find(|candidate| candidate.exists())
+}
+
+/// Comprobar si `items` caben y se pueden crear bajo `destination`.
+/// `reserved` es lo que ya tienen encolado otras transferencias del mismo dispositivo.
+pub fn check(items: &[TransferItem], destination: &Path, device: &DeviceInfo, reserved: u64) -> PreflightReport {
+    let filesystem = device.filesystem.as_deref().unwrap_or("");
+    let mut report = PreflightReport {
+        available_bytes: device.available_space.saturating_sub(reserved),
+        ..Default::default()
+    };
+
+    if device.is_read_only {
+        report.problems.push(PreflightProblem::ReadOnly);
+    } else if let Some(dir) = nearest_existing(destination) {
+        if nix::unistd::access(dir, nix::unistd::AccessFlags::W_OK).is_err() {
+            report.problems.push(PreflightProblem::NotWritable(dir.to_path_buf()));
+        }
+    }
+
+    let mut new_bytes = 0;
+    let mut freed_bytes = 0;
+    let mut largest_overwrite = 0;
+    let mut pending: Vec<&TransferItem> = items.iter().collect();
+
+    while let Some(item) = pending.pop() {
+        let target = destination.join(&item.relative_path);
+
+        if has_windows_names(filesystem) {
+            let name = item.relative_path.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+            if let Some(reason) = invalid_name_reason(&name, filesystem) {
+                report.problems.push(PreflightProblem::InvalidName { path: item.source_path.clone(), reason });
+            }
+        }
+
+        match item.item_type {
+            ItemType::Directory => {
+                // Si ya existe se fusiona y no ocupa más
+                if !target.is_dir() {
+                    new_bytes += device.block_size;
+                }
+                pending.extend(item.children.iter());
+            }
+            ItemType::File => {
+                if is_fat(filesystem) && item.size > FAT32_MAX_FILE_SIZE {
+                    report.problems.push(PreflightProblem::FileTooLarge {
+                        path: item.source_path.clone(),
+                        size: item.size,
+                        limit: FAT32_MAX_FILE_SIZE,
+                    });
+                }
+
+                new_bytes += round_up(item.size, device.block_size);
+
+                // El archivo viejo se libera al renombrar el nuevo encima, así
+                // que durante la copia del mayor de ellos conviven los dos
+                let existing = existing_allocation(&target);
+                freed_bytes += existing;
+                largest_overwrite = largest_overwrite.max(existing);
+            }
+            _ => {}
+        }
+    }
+
+    report.required_bytes = (new_bytes + largest_overwrite).saturating_sub(freed_bytes);
+
+    if report.required_bytes > report.available_bytes {
+        report.problems.push(PreflightProblem::InsufficientSpace {
+            required: report.required_bytes,
+            available: report.available_bytes,
+        });
+    }
+
+    report
+}
+
+/// Comprobar una transferencia contra el estado actual de su destino
+pub async fn check_job(job: &crate::core::device_queue::TransferJob, reserved: u64) -> PreflightReport {
+    let device = crate::core::device_detector::DeviceMonitor::new().detect_device(&job.destination).await;
+    check(&job.root_items, &job.destination, &device, reserved)
+}
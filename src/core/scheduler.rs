@@ -0,0 +1,554 @@
+// src/core/scheduler.rs
+// Planificador de las colas por dispositivo: una transferencia a la vez por
+// dispositivo físico (varias en SSD) y dispositivos distintos en paralelo
+use std::collections::{HashMap, HashSet};
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::{mpsc, Mutex, Notify};
+
+use crate::core::daemon::DaemonNotification;
+use crate::core::device_detector::{DeviceInfo, DeviceMonitor, DeviceType};
+use crate::core::device_queue::{DeviceQueue, QueuePriority, TransferJob, TransferProgress};
+use crate::core::disk_space::{SpaceDecision, SpaceRequest};
+use crate::core::error_recovery::{CopyError, IoOperation, IoResultExt};
+
+/// Cada cuánto se revisan las colas aunque nadie avise, p. ej. tras añadir
+/// una transferencia directamente a una `DeviceQueue` o reanudar una cola
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Clave de la cola de un destino: el dispositivo (st_dev) del directorio
+/// existente más cercano, de modo que dos carpetas del mismo disco comparten cola
+pub fn device_key(path: &Path) -> String {
+    let mut current = Some(path);
+
+    while let Some(candidate) = current {
+        if let Ok(metadata) = std::fs::metadata(candidate) {
+            let dev = metadata.dev();
+            return format!("{}:{}", nix::sys::stat::major(dev), nix::sys::stat::minor(dev));
+        }
+        current = candidate.parent();
+    }
+
+    path.display().to_string()
+}
+
+/// Cambio de prioridad pedido desde otro proceso, p. ej. `copymaster prioritize`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PriorityRequest {
+    pub job_id: u64,
+    pub priority: QueuePriority,
+}
+
+/// Directorio donde otros procesos dejan peticiones para la instancia en marcha
+pub fn requests_dir() -> Option<PathBuf> {
+    crate::core::config::ConfigManager::config_dir().map(|dir| dir.join("requests"))
+}
+
+/// Pedir a la instancia en marcha que cambie la prioridad de un job en cola.
+/// Se escribe con write + rename para que nunca se lea a medias.
+pub fn request_priority_change(dir: &Path, job_id: u64, priority: QueuePriority) -> Result<(), CopyError> {
+    let request = PriorityRequest { job_id, priority };
+    let content = serde_json::to_vec(&request).map_err(|e| CopyError::Io(e.to_string()))?;
+    let path = dir.join(format!("{}.priority", job_id));
+    let tmp = dir.join(format!(".{}.priority.tmp", job_id));
+
+    std::fs::create_dir_all(dir).io_context(IoOperation::CreateDir, dir)?;
+    std::fs::write(&tmp, content).io_context(IoOperation::Write, &tmp)?;
+    std::fs::rename(&tmp, &path).io_context(IoOperation::Rename, &path)
+}
+
+pub struct QueueScheduler {
+    device_queues: Arc<Mutex<HashMap<String, DeviceQueue>>>,
+    config_manager: Arc<Mutex<crate::core::config::ConfigManager>>,
+    progress_sender: Option<mpsc::Sender<(u64, TransferProgress)>>,
+    requests_dir: Option<PathBuf>,
+    wakeup: Notify,
+    running: AtomicUsize,
+    // Colas cuyo número de transferencias simultáneas ya se ha calculado
+    configured: std::sync::Mutex<HashSet<String>>,
+    // Diálogo de errores de la interfaz; sin él se aplica `unattended_error_action`
+    error_dialog: std::sync::Mutex<Option<mpsc::Sender<crate::ui::error_dialog::ErrorDialogRequest>>>,
+    // Diálogo de destino lleno; sin él solo se espera a que haya espacio
+    space_dialog: std::sync::Mutex<Option<mpsc::Sender<SpaceRequest>>>,
+}
+
+impl QueueScheduler {
+    pub fn new(config_manager: Arc<Mutex<crate::core::config::ConfigManager>>) -> Self {
+        Self {
+            device_queues: Arc::new(Mutex::new(HashMap::new())),
+            config_manager,
+            progress_sender: None,
+            requests_dir: requests_dir(),
+            wakeup: Notify::new(),
+            running: AtomicUsize::new(0),
+            configured: std::sync::Mutex::new(HashSet::new()),
+            error_dialog: std::sync::Mutex::new(None),
+            space_dialog: std::sync::Mutex::new(None),
+        }
+    }
+
+    /// Preguntar al usuario por los errores que no se resuelven reintentando.
+    /// Se puede fijar después de arrancar, cuando ya existe la ventana.
+    pub fn set_error_dialog(&self, sender: mpsc::Sender<crate::ui::error_dialog::ErrorDialogRequest>) {
+        *self.error_dialog.lock().unwrap_or_else(|p| p.into_inner()) = Some(sender);
+    }
+
+    /// Ofrecer al usuario alternativas a esperar cuando un destino se llena
+    pub fn set_space_dialog(&self, sender: mpsc::Sender<SpaceRequest>) {
+        *self.space_dialog.lock().unwrap_or_else(|p| p.into_inner()) = Some(sender);
+    }
+
+    /// Compartir las colas con la interfaz (panel de colas, arrastrar y soltar)
+    pub fn with_device_queues(mut self, device_queues: Arc<Mutex<HashMap<String, DeviceQueue>>>) -> Self {
+        self.device_queues = device_queues;
+        self
+    }
+
+    /// Reenviar el progreso de cada job, identificado por su id
+    pub fn with_progress_sender(mut self, sender: mpsc::Sender<(u64, TransferProgress)>) -> Self {
+        self.progress_sender = Some(sender);
+        self
+    }
+
+    /// Directorio del que se recogen las peticiones de otros procesos
+    pub fn with_requests_dir(mut self, dir: Option<PathBuf>) -> Self {
+        self.requests_dir = dir;
+        self
+    }
+
+    pub fn device_queues(&self) -> Arc<Mutex<HashMap<String, DeviceQueue>>> {
+        self.device_queues.clone()
+    }
+
+    /// Transferencias copiándose ahora mismo en todas las colas
+    pub fn active_jobs(&self) -> usize {
+        self.running.load(Ordering::SeqCst)
+    }
+
+    /// Poner un job en la cola del dispositivo de su destino. Devuelve la clave de la cola.
+    pub async fn enqueue(&self, job: TransferJob) -> String {
+        let key = device_key(&job.destination);
+
+        {
+            let mut queues = self.device_queues.lock().await;
+            queues
+                .entry(key.clone())
+                .or_insert_with(|| DeviceQueue::new(job.destination.clone()))
+                .enqueue(job);
+        }
+
+        self.wake();
+        key
+    }
+
+    /// Crear y encolar un job a partir de lo soltado sobre un dispositivo
+    pub async fn submit(&self, request: crate::core::drag_drop::TransferRequest) -> Result<u64, CopyError> {
+        let job = TransferJob::from_request(request).await?;
+
+        let reserved = {
+            let queues = self.device_queues.lock().await;
+            queues.get(&device_key(&job.destination)).map(|queue| queue.queued_bytes()).unwrap_or(0)
+        };
+        let report = crate::core::preflight::check_job(&job, reserved).await;
+        if !report.is_ok() {
+            return Err(CopyError::PreflightFailed(report.summary()));
+        }
+
+        let id = job.id;
+        self.enqueue(job).await;
+        Ok(id)
+    }
+
+    /// Cambiar la prioridad de un job que espera en cualquiera de las colas
+    pub async fn set_priority(&self, job_id: u64, priority: QueuePriority) -> bool {
+        let found = {
+            let mut queues = self.device_queues.lock().await;
+            queues.values_mut().any(|queue| queue.set_priority(job_id, priority.clone()))
+        };
+
+        if found {
+            log::info!("Prioridad de la transferencia {}: {:?}", job_id, priority);
+            self.wake();
+        }
+        found
+    }
+
+    /// Aplicar las peticiones de prioridad pendientes y borrarlas
+    async fn apply_priority_requests(&self) {
+        let Some(dir) = &self.requests_dir else {
+            return;
+        };
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return;
+        };
+
+        let requests: Vec<PathBuf> = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| path.extension().map(|ext| ext == "priority").unwrap_or(false))
+            .collect();
+
+        for path in requests {
+            let request = std::fs::read(&path)
+                .ok()
+                .and_then(|content| serde_json::from_slice::<PriorityRequest>(&content).ok());
+            let _ = std::fs::remove_file(&path);
+
+            match request {
+                Some(request) => {
+                    if !self.set_priority(request.job_id, request.priority).await {
+                        log::warn!("La transferencia {} no está en cola; no se cambia su prioridad", request.job_id);
+                    }
+                }
+                None => log::warn!("Petición de prioridad ilegible: {}", path.display()),
+            }
+        }
+    }
+
+    /// Revisar las colas sin esperar a la siguiente vuelta
+    pub fn wake(&self) {
+        self.wakeup.notify_one();
+    }
+
+    /// Arrancar el bucle del planificador
+    pub fn spawn(self: &Arc<Self>) -> tokio::task::JoinHandle<()> {
+        let scheduler = self.clone();
+
+        tokio::spawn(async move {
+            loop {
+                scheduler.apply_priority_requests().await;
+                scheduler.dispatch().await;
+
+                tokio::select! {
+                    _ = scheduler.wakeup.notified() => {}
+                    _ = tokio::time::sleep(POLL_INTERVAL) => {}
+                }
+            }
+        })
+    }
+
+    /// Lanzar todo lo que las colas admitan ahora mismo
+    async fn dispatch(self: &Arc<Self>) {
+        let config = self.config_manager.lock().await.get_config().clone();
+        let mut queues = self.device_queues.lock().await;
+
+        for (key, queue) in queues.iter_mut() {
+            let is_new = self.configured.lock().unwrap_or_else(|p| p.into_inner()).insert(key.clone());
+            if is_new {
+                let info = DeviceMonitor::new().detect_device(&queue.device_path).await;
+                queue.max_concurrent = Self::concurrency_for(&info, &config);
+                queue.device_id = info.device_id;
+            }
+
+            while let Some(job) = queue.start_next() {
+                log::info!("Iniciando transferencia {} ({}) en {}", job.id, job.transfer_name, queue.device_name);
+                self.running.fetch_add(1, Ordering::SeqCst);
+
+                let scheduler = self.clone();
+                let key = key.clone();
+                let queue_limiter = queue.rate_limiter.clone();
+                let config = config.clone();
+                tokio::spawn(async move {
+                    scheduler.run_job(key, job, queue_limiter, config).await;
+                });
+            }
+        }
+    }
+
+    /// En un SSD varias copias a la vez no provocan búsquedas del cabezal
+    fn concurrency_for(info: &DeviceInfo, config: &crate::core::config::AppConfig) -> usize {
+        match info.device_type {
+            DeviceType::NVMeSSD | DeviceType::SataSSD | DeviceType::RAMDisk => config.ssd_parallel_transfers.max(1),
+            _ => 1,
+        }
+    }
+
+    /// Atender las conexiones y desconexiones que detecte `monitor`
+    pub fn watch_devices(self: &Arc<Self>, mut monitor: DeviceMonitor) -> tokio::task::JoinHandle<()> {
+        let scheduler = self.clone();
+        let (sender, mut receiver) = mpsc::channel(32);
+
+        tokio::spawn(async move {
+            monitor.start_monitoring(sender).await;
+        });
+
+        tokio::spawn(async move {
+            while let Some(notification) = receiver.recv().await {
+                match notification {
+                    DaemonNotification::DeviceDisconnected(id) => scheduler.device_disconnected(&id).await,
+                    DaemonNotification::DeviceConnected(id) => scheduler.device_connected(&id).await,
+                    _ => {}
+                }
+            }
+        })
+    }
+
+    /// Pausar las colas cuyo destino estaba en el dispositivo que ha desaparecido;
+    /// la copia en curso se detiene en el siguiente chunk
+    pub async fn device_disconnected(&self, device_id: &str) {
+        let mut queues = self.device_queues.lock().await;
+
+        for queue in queues.values_mut().filter(|queue| queue.device_id.as_deref() == Some(device_id)) {
+            log::warn!("{} se ha desconectado; cola en pausa hasta que vuelva", queue.device_name);
+            queue.pause_disconnected();
+        }
+    }
+
+    /// Reanudar las colas pausadas por la desconexión de este dispositivo,
+    /// siempre que vuelva a estar montado donde lo esperan sus transferencias.
+    /// Las colas que ahora están sobre él vuelven a detectarlo antes de lanzar nada.
+    pub async fn device_connected(&self, device_id: &str) {
+        let mut queues = self.device_queues.lock().await;
+        let monitor = DeviceMonitor::new();
+        let mut configured = self.configured.lock().unwrap_or_else(|p| p.into_inner());
+
+        for (key, queue) in queues.iter_mut() {
+            let mounted_here = monitor.find_mount(&queue.device_path)
+                .map(|mount| monitor.device_id(&mount) == device_id)
+                .unwrap_or(false);
+            let expected = queue.device_id.as_deref() == Some(device_id);
+
+            if queue.auto_paused && expected {
+                if mounted_here {
+                    log::info!("{} ha vuelto; reanudando su cola", queue.device_name);
+                    queue.resume();
+                } else {
+                    log::warn!("{} ha vuelto en otro punto de montaje; la cola sigue en pausa", queue.device_name);
+                }
+            }
+
+            // Puede ser otro disco en el mismo punto de montaje: su id y su
+            // concurrencia se recalculan en el siguiente reparto. Una cola que
+            // espera a su propio disco no se reasigna a otro.
+            if mounted_here && (expected || !queue.auto_paused) {
+                configured.remove(key);
+            }
+        }
+
+        drop(configured);
+        drop(queues);
+        self.wake();
+    }
+
+    /// Ejecutar un job con su propio motor y devolverlo a su cola al terminar
+    async fn run_job(
+        self: Arc<Self>,
+        key: String,
+        mut job: TransferJob,
+        queue_limiter: Arc<crate::core::throttle::RateLimiter>,
+        config: crate::core::config::AppConfig,
+    ) {
+        let mut monitor = DeviceMonitor::new().with_speed_cache(config.device_speeds.clone());
+        let dest_info = monitor.detect_device(&job.destination).await;
+        let source_info = match job.root_items.first() {
+            Some(item) => monitor.detect_device(&item.source_path).await,
+            None => dest_info.clone(),
+        };
+
+        let optimizer = crate::core::optimizer::CopyOptimizer::new();
+        let strategy = optimizer.get_optimal_strategy(&source_info, &dest_info);
+        let mut options = optimizer.apply_strategy(&strategy, &config.default_copy_options);
+        options.verify_after_copy = job.options.verify_after_copy;
+        options.preserve_attributes = job.options.preserve_attributes;
+        // Intercalar lectura y escritura solo tiene sentido entre discos distintos
+        let same_device = job.root_items.first()
+            .map(|item| device_key(&item.source_path) == device_key(&job.destination))
+            .unwrap_or(true);
+        options.interleave_io &= !same_device;
+
+        // Con límite de ancho de banda la transferencia no dice nada del disco
+        let throttled = options.throttle_mbps.is_some()
+            || job.rate_limiter.limit_mbps().is_some()
+            || queue_limiter.limit_mbps().is_some()
+            || crate::core::throttle::global_limiter().limit_mbps().is_some();
+
+        let mut recovery = crate::core::error_recovery::ErrorRecovery::from_policies(config.retry_policies.clone(), true)
+            .with_control(job.control.clone());
+        recovery.add_temp_root(job.destination.clone());
+
+        let mut engine = crate::core::local_engine::LocalCopyEngine::new(options)
+            .with_rate_limiter(job.rate_limiter.clone())
+            .with_rate_limiter(queue_limiter)
+            .with_control(job.control.clone())
+            .with_error_recovery(Arc::new(recovery));
+
+        if let Some(dir) = crate::core::journal::TransferJournal::journal_dir() {
+            match crate::core::journal::TransferJournal::for_job(&dir, &job) {
+                Ok(journal) => engine = engine.with_journal(Arc::new(journal)),
+                Err(e) => log::warn!("Transferencia {} sin diario, no se podrá reanudar: {}", job.id, e),
+            }
+        }
+
+        if job.ui_error_channel.is_none() {
+            job.ui_error_channel = self.error_dialog.lock().unwrap_or_else(|p| p.into_inner()).clone();
+        }
+
+        // Un destino lleno pausa toda la cola, y eso solo lo puede hacer el planificador
+        let (space_sender, mut space_receiver) = mpsc::channel(1);
+        job.space_channel = Some(space_sender);
+
+        let job_id = job.id;
+        let receiver = job.progress_receiver.clone();
+        let mut receiver = receiver.lock().await;
+        let executor = crate::core::tree_copy::TreeCopyExecutor::new(&engine, dest_info.clone(), config)
+            .with_schedule_order(crate::core::locality::ScheduleOrder::for_device(&source_info.device_type));
+
+        // El canal del job es acotado: hay que vaciarlo mientras se copia
+        let result = {
+            let execution = executor.execute(&mut job);
+            tokio::pin!(execution);
+
+            loop {
+                tokio::select! {
+                    result = &mut execution => break result,
+                    Some(progress) = receiver.recv() => self.forward(job_id, progress).await,
+                    Some(request) = space_receiver.recv() => {
+                        tokio::spawn(self.clone().wait_for_space(key.clone(), request));
+                    }
+                }
+            }
+        };
+
+        while let Ok(progress) = receiver.try_recv() {
+            self.forward(job_id, progress).await;
+        }
+        drop(receiver);
+
+        job.space_channel = None;
+        log::info!("Transferencia {} terminada: {:?}", job_id, result.status);
+
+        if let Some(destination) = job.relocate_to.take() {
+            self.relocate(&job, destination).await;
+        }
+
+        if !throttled && matches!(result.status, crate::core::device_queue::TransferStatus::Completed) {
+            self.learn_speed(&source_info, &dest_info, &result).await;
+        }
+
+        {
+            let mut queues = self.device_queues.lock().await;
+            match queues.get_mut(&key) {
+                Some(queue) => queue.finish_transfer(job, result),
+                None => log::warn!("La cola {} desapareció durante la transferencia {}", key, job_id),
+            }
+        }
+
+        self.running.fetch_sub(1, Ordering::SeqCst);
+        self.wake();
+    }
+
+    /// Pausar la cola de un destino lleno hasta que haya espacio o el usuario
+    /// decida otra cosa, y contestar al job con lo que se haya decidido
+    async fn wait_for_space(self: Arc<Self>, key: String, request: SpaceRequest) {
+        let SpaceRequest { transfer_name, shortage, control, response_sender } = request;
+
+        {
+            let mut queues = self.device_queues.lock().await;
+            if let Some(queue) = queues.get_mut(&key) {
+                log::warn!("{}: cola de {} en pausa hasta que haya espacio", shortage, queue.device_name);
+                queue.pause_for_space(shortage.clone());
+            }
+        }
+
+        crate::ui::tray_icon::notify(
+            "Destino lleno",
+            &format!("{}: {}. La copia seguirá sola cuando haya espacio.", transfer_name, shortage),
+            Some("drive-harddisk"),
+        );
+
+        let (decision_sender, decision_receiver) = tokio::sync::oneshot::channel();
+        let dialog = self.space_dialog.lock().unwrap_or_else(|p| p.into_inner()).clone();
+        if let Some(dialog) = dialog {
+            let _ = dialog.send(SpaceRequest {
+                transfer_name,
+                shortage: shortage.clone(),
+                control: control.clone(),
+                response_sender: decision_sender,
+            }).await;
+        }
+
+        // Cerrar el diálogo sin elegir nada es seguir esperando
+        let chosen = async {
+            match decision_receiver.await {
+                Ok(decision) => decision,
+                Err(_) => std::future::pending().await,
+            }
+        };
+
+        let decision = tokio::select! {
+            waited = crate::core::disk_space::wait_for_space(&shortage.destination, shortage.required, &control) => {
+                match waited {
+                    Ok(()) => SpaceDecision::Resume,
+                    Err(_) => SpaceDecision::Abort,
+                }
+            }
+            decision = chosen => decision,
+        };
+
+        log::info!("Destino lleno en la cola {}: {:?}", key, decision);
+        {
+            let mut queues = self.device_queues.lock().await;
+            if let Some(queue) = queues.get_mut(&key) {
+                if queue.space_shortage.is_some() {
+                    queue.resume();
+                }
+            }
+        }
+
+        let _ = response_sender.send(decision);
+        self.wake();
+    }
+
+    /// Encolar en `destination` lo que `job` no llegó a copiar, con el mismo id
+    async fn relocate(&self, job: &TransferJob, destination: PathBuf) {
+        let mut items = crate::core::disk_space::unfinished_items(&job.root_items);
+        items.extend(job.take_incoming_items());
+
+        let mut moved = TransferJob::new(job.id, items, destination, job.transfer_name.clone(), job.priority.clone());
+        moved.rate_limiter.set_limit_mbps(job.rate_limiter.limit_mbps());
+        moved.options = job.options.clone();
+
+        log::info!("Transferencia {}: lo que falta sigue en {}", job.id, moved.destination.display());
+        self.enqueue(moved).await;
+    }
+
+    /// Aprender de una transferencia terminada. El rendimiento lo marca el más
+    /// lento de los dos extremos, así que la muestra es para el que se creía más lento.
+    /// Solo cuenta el tiempo moviendo datos, y nada si hubo pausas o esperas.
+    async fn learn_speed(&self, source: &DeviceInfo, dest: &DeviceInfo, result: &crate::core::drag_drop::TransferResult) {
+        if result.interrupted {
+            return;
+        }
+        let Some(mbps) = crate::core::benchmark::transfer_throughput(result.active_bytes, result.active_duration) else {
+            return;
+        };
+
+        let source_is_slower = match (source.estimated_speed_mbps, dest.estimated_speed_mbps) {
+            (Some(source_speed), Some(dest_speed)) => source_speed < dest_speed,
+            (Some(_), None) => true,
+            _ => false,
+        };
+        let bottleneck = if source_is_slower { source } else { dest };
+
+        let Some(device_id) = &bottleneck.device_id else {
+            return;
+        };
+
+        log::debug!("Velocidad observada en {}: {:.1} MB/s", device_id, mbps);
+        let mut config_manager = self.config_manager.lock().await;
+        if let Err(e) = config_manager.update_device_speed(device_id, |speed| speed.record_sample(mbps)) {
+            log::warn!("No se pudo guardar la velocidad de {}: {}", device_id, e);
+        }
+    }
+
+    async fn forward(&self, job_id: u64, progress: TransferProgress) {
+        if let Some(sender) = &self.progress_sender {
+            let _ = sender.send((job_id, progress)).await;
+        }
+    }
+}
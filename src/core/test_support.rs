@@ -0,0 +1,32 @@
+// src/core/test_support.rs
+// Utilidades de las pruebas unitarias. No depende de nada del crate para que
+// copymaster-helper también pueda incluirlo.
+use std::path::{Path, PathBuf};
+
+/// Directorio temporal vacío para una prueba; se borra al soltarlo
+pub struct ScratchDir(PathBuf);
+
+impl ScratchDir {
+    /// `name` distingue las pruebas que corren a la vez en el mismo proceso
+    pub fn new(name: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("copymaster-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).expect("directorio temporal de la prueba");
+        // Sin enlaces en la ruta, como las que acepta el ayudante
+        Self(dir.canonicalize().expect("ruta del directorio temporal"))
+    }
+}
+
+impl std::ops::Deref for ScratchDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for ScratchDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
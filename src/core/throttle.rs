@@ -0,0 +1,250 @@
+// src/core/throttle.rs
+// Limitación de ancho de banda con token bucket
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use nix::libc;
+
+/// Bytes por MB en los límites configurados
+const BYTES_PER_MB: f64 = 1_000_000.0;
+
+/// Ráfaga máxima permitida, en segundos de tasa
+const BURST_SECONDS: f64 = 0.25;
+
+/// Cada cuánto se revisa la tasa mientras se espera, para aplicar cambios en vivo
+const WAIT_SLICE: Duration = Duration::from_millis(100);
+
+/// Cada cuánto se vuelven a evaluar los horarios de ancho de banda
+const SCHEDULE_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+#[derive(Debug)]
+struct BucketState {
+    rate_bps: Option<f64>,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl BucketState {
+    fn refill(&mut self, rate: f64) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * rate).min(rate * BURST_SECONDS);
+        self.last_refill = now;
+    }
+}
+
+/// Token bucket compartido entre hilos. La tasa se puede cambiar mientras
+/// hay copias en curso; `None` significa sin límite.
+#[derive(Debug)]
+pub struct RateLimiter {
+    state: Mutex<BucketState>,
+}
+
+impl RateLimiter {
+    pub fn new(limit_mbps: Option<f64>) -> Self {
+        let rate_bps = to_bps(limit_mbps);
+
+        Self {
+            state: Mutex::new(BucketState {
+                rate_bps,
+                tokens: rate_bps.map(|rate| rate * BURST_SECONDS).unwrap_or(0.0),
+                last_refill: Instant::now(),
+            }),
+        }
+    }
+
+    pub fn unlimited() -> Self {
+        Self::new(None)
+    }
+
+    /// Límite actual en MB/s
+    pub fn limit_mbps(&self) -> Option<f64> {
+        self.lock().rate_bps.map(|rate| rate / BYTES_PER_MB)
+    }
+
+    pub fn is_limited(&self) -> bool {
+        self.lock().rate_bps.is_some()
+    }
+
+    /// Cambiar el límite; afecta también a quien esté esperando ahora mismo
+    pub fn set_limit_mbps(&self, limit_mbps: Option<f64>) {
+        let mut state = self.lock();
+        let rate_bps = to_bps(limit_mbps);
+
+        if let Some(rate) = state.rate_bps {
+            state.refill(rate);
+        }
+        state.rate_bps = rate_bps;
+        state.last_refill = Instant::now();
+
+        match rate_bps {
+            Some(rate) => state.tokens = state.tokens.min(rate * BURST_SECONDS),
+            None => state.tokens = 0.0,
+        }
+    }
+
+    /// Consumir `bytes` del bucket, bloqueando el hilo hasta que la tasa lo permita.
+    /// Solo debe llamarse desde hilos bloqueantes, nunca desde el runtime async.
+    pub fn acquire(&self, bytes: u64) {
+        {
+            let mut state = self.lock();
+            let Some(rate) = state.rate_bps else {
+                return;
+            };
+            state.refill(rate);
+            // Se permite deuda: quien pide más que la ráfaga espera a saldarla
+            state.tokens -= bytes as f64;
+        }
+
+        loop {
+            let wait = {
+                let mut state = self.lock();
+                let Some(rate) = state.rate_bps else {
+                    return;
+                };
+                state.refill(rate);
+                if state.tokens >= 0.0 {
+                    return;
+                }
+                Duration::from_secs_f64(-state.tokens / rate)
+            };
+
+            std::thread::sleep(wait.min(WAIT_SLICE));
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BucketState> {
+        self.state.lock().unwrap_or_else(|p| p.into_inner())
+    }
+}
+
+fn to_bps(limit_mbps: Option<f64>) -> Option<f64> {
+    limit_mbps
+        .filter(|limit| *limit > 0.0 && limit.is_finite())
+        .map(|limit| limit * BYTES_PER_MB)
+}
+
+/// Limitador global, compartido por todas las transferencias
+pub fn global_limiter() -> Arc<RateLimiter> {
+    static GLOBAL: OnceLock<Arc<RateLimiter>> = OnceLock::new();
+    GLOBAL.get_or_init(|| Arc::new(RateLimiter::unlimited())).clone()
+}
+
+/// Cadena de limitadores que se aplican a la vez (transferencia, cola del
+/// dispositivo y global). El más restrictivo marca el ritmo.
+#[derive(Debug, Clone, Default)]
+pub struct Throttle {
+    limiters: Vec<Arc<RateLimiter>>,
+}
+
+impl Throttle {
+    pub fn new(limiters: Vec<Arc<RateLimiter>>) -> Self {
+        Self { limiters }
+    }
+
+    pub fn with_limiter(mut self, limiter: Arc<RateLimiter>) -> Self {
+        self.limiters.push(limiter);
+        self
+    }
+
+    pub fn is_limited(&self) -> bool {
+        self.limiters.iter().any(|limiter| limiter.is_limited())
+    }
+
+    pub fn acquire(&self, bytes: u64) {
+        for limiter in &self.limiters {
+            limiter.acquire(bytes);
+        }
+    }
+}
+
+/// Límite de ancho de banda durante una franja horaria, p. ej. 20 MB/s de 09:00 a 18:00
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BandwidthSchedule {
+    pub start: String, // "HH:MM", hora local
+    pub end: String,   // "HH:MM"; si es menor que `start` la franja cruza la medianoche
+    pub limit_mbps: f64,
+    #[serde(default)]
+    pub days: Vec<u8>, // 0 = domingo ... 6 = sábado; vacío = todos los días
+}
+
+impl BandwidthSchedule {
+    /// Si la franja está activa en `weekday` (0 = domingo) al minuto `minute` del día
+    pub fn is_active_at(&self, weekday: u8, minute: u16) -> bool {
+        let (Some(start), Some(end)) = (parse_hhmm(&self.start), parse_hhmm(&self.end)) else {
+            return false;
+        };
+        let applies_to = |day: u8| self.days.is_empty() || self.days.contains(&day);
+
+        if start <= end {
+            applies_to(weekday) && minute >= start && minute < end
+        } else if minute >= start {
+            applies_to(weekday)
+        } else {
+            // Tramo posterior a la medianoche: pertenece a la franja del día anterior
+            minute < end && applies_to((weekday + 6) % 7)
+        }
+    }
+}
+
+fn parse_hhmm(value: &str) -> Option<u16> {
+    let (hours, minutes) = value.trim().split_once(':')?;
+    let hours: u16 = hours.parse().ok()?;
+    let minutes: u16 = minutes.parse().ok()?;
+
+    if hours > 24 || minutes > 59 || (hours == 24 && minutes > 0) {
+        return None;
+    }
+    Some(hours * 60 + minutes)
+}
+
+/// Día de la semana (0 = domingo) y minuto del día en hora local
+pub fn local_time() -> (u8, u16) {
+    let now = unsafe { libc::time(std::ptr::null_mut()) };
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+
+    if unsafe { libc::localtime_r(&now, &mut tm) }.is_null() {
+        return (0, 0);
+    }
+    (tm.tm_wday as u8, (tm.tm_hour * 60 + tm.tm_min) as u16)
+}
+
+/// Límite efectivo: el menor entre el límite fijo y las franjas activas
+pub fn scheduled_limit(base_limit_mbps: Option<f64>, schedules: &[BandwidthSchedule]) -> Option<f64> {
+    let (weekday, minute) = local_time();
+
+    schedules
+        .iter()
+        .filter(|schedule| schedule.is_active_at(weekday, minute))
+        .map(|schedule| schedule.limit_mbps)
+        .chain(base_limit_mbps)
+        .filter(|limit| *limit > 0.0)
+        .reduce(f64::min)
+}
+
+/// Mantener el limitador global de acuerdo con la configuración y sus horarios.
+/// Los cambios de configuración se recogen en la siguiente revisión.
+pub fn spawn_schedule_task(
+    config_manager: Arc<tokio::sync::Mutex<crate::core::config::ConfigManager>>,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let limiter = global_limiter();
+        let mut interval = tokio::time::interval(SCHEDULE_CHECK_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            let limit = {
+                let manager = config_manager.lock().await;
+                let config = manager.get_config();
+                scheduled_limit(config.bandwidth_limit_mbps, &config.bandwidth_schedules)
+            };
+
+            if limit != limiter.limit_mbps() {
+                log::info!("Límite global de ancho de banda: {:?} MB/s", limit);
+                limiter.set_limit_mbps(limit);
+            }
+        }
+    })
+}
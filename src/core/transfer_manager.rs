@@ -1,38 +1,105 @@
 // src/core/transfer_manager.rs
-use std::collections::HashMap;
 use std::sync::Arc;
-use tokio::sync::Mutex;
-use uuid::Uuid;
 
 pub struct TransferManager {
-    transfers: HashMap<Uuid, crate::core::device_queue::TransferJob>,
-    active_transfers: Vec<Uuid>,
+    scheduler: Option<Arc<crate::core::scheduler::QueueScheduler>>,
     options: crate::core::copy_engine::CopyOptions,
-    cancel_flag: Arc<std::sync::atomic::AtomicBool>,
+    control: Arc<crate::core::control::TransferControl>, // Control global de todas las colas
+    // Transferencias que quedaron a medias en una ejecución anterior
+    interrupted: Vec<crate::core::journal::JournalRecord>,
 }
 
 impl TransferManager {
     pub fn new() -> Self {
         Self {
-            transfers: HashMap::new(),
-            active_transfers: Vec::new(),
+            scheduler: None,
             options: crate::core::copy_engine::CopyOptions::default(),
-            cancel_flag: Arc::new(std::sync::atomic::AtomicBool::new(false)),
+            control: crate::core::control::global_control(),
+            interrupted: Vec::new(),
         }
     }
     
+    /// Planificador al que se envían las transferencias
+    pub fn with_scheduler(mut self, scheduler: Arc<crate::core::scheduler::QueueScheduler>) -> Self {
+        self.scheduler = Some(scheduler);
+        self
+    }
+    
+    /// Buscar en el diario las transferencias que no terminaron
+    pub fn load_interrupted(&mut self) -> &[crate::core::journal::JournalRecord] {
+        self.interrupted = crate::core::journal::TransferJournal::journal_dir()
+            .map(|dir| crate::core::journal::TransferJournal::interrupted(&dir))
+            .unwrap_or_default();
+        &self.interrupted
+    }
+    
+    pub fn interrupted_transfers(&self) -> &[crate::core::journal::JournalRecord] {
+        &self.interrupted
+    }
+    
+    /// Volver a encolar una transferencia interrumpida; los archivos a medias
+    /// continúan desde el último offset registrado
+    pub async fn resume_interrupted(&mut self, job_id: u64) -> bool {
+        let Some(index) = self.interrupted.iter().position(|record| record.job_id == job_id) else {
+            return false;
+        };
+        
+        let record = self.interrupted.remove(index);
+        log::info!("Reanudando transferencia {} ({})", record.job_id, record.transfer_name);
+        self.add_transfer(record.restore_job()).await;
+        true
+    }
+    
+    /// Olvidar una transferencia interrumpida y borrar sus temporales
+    pub fn discard_interrupted(&mut self, job_id: u64) -> Result<(), crate::core::error_recovery::CopyError> {
+        if let Some(record) = self.interrupted.iter().find(|record| record.job_id == job_id) {
+            for part in record.part_files() {
+                if let Err(e) = std::fs::remove_file(&part) {
+                    if e.kind() != std::io::ErrorKind::NotFound {
+                        log::warn!("No se pudo borrar {}: {}", part.display(), e);
+                    }
+                }
+            }
+        }
+        self.interrupted.retain(|record| record.job_id != job_id);
+        
+        match crate::core::journal::TransferJournal::journal_dir() {
+            Some(dir) => crate::core::journal::TransferJournal::discard(&dir, job_id),
+            None => Ok(()),
+        }
+    }
+    
+    /// Encolar una transferencia en la cola del dispositivo de destino
     pub async fn add_transfer(&mut self, transfer: crate::core::device_queue::TransferJob) {
-        let id = Uuid::new_v4();
-        // self.transfers.insert(id, transfer);
-        // self.active_transfers.push(id);
+        match &self.scheduler {
+            Some(scheduler) => {
+                scheduler.enqueue(transfer).await;
+            }
+            None => log::error!("Sin planificador: la transferencia {} no se ejecutará", transfer.id),
+        }
     }
     
+    /// Cancela lo que esté copiándose o encolado; lo que se añada después no se ve afectado
     pub async fn cancel_all(&mut self) {
-        self.cancel_flag.store(true, std::sync::atomic::Ordering::SeqCst);
-        self.active_transfers.clear();
+        self.control.cancel();
+    }
+    
+    pub fn pause_all(&self) {
+        self.control.pause();
+    }
+    
+    pub fn resume_all(&self) {
+        self.control.resume();
+    }
+    
+    pub fn is_paused(&self) -> bool {
+        self.control.is_paused()
     }
     
     pub fn has_active_transfers(&self) -> bool {
-        !self.active_transfers.is_empty()
+        self.scheduler
+            .as_ref()
+            .map(|scheduler| scheduler.active_jobs() > 0)
+            .unwrap_or(false)
     }
 }
\ No newline at end of file
@@ -0,0 +1,796 @@
+// src/core/tree_copy.rs
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::os::unix::fs::{FileTypeExt, MetadataExt};
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::time::Instant;
+
+use crate::core::copy_engine::SymlinkPolicy;
+use crate::core::disk_space::{SpaceDecision, SpaceShortage};
+use crate::core::device_queue::{ItemTransferStatus, ItemType, SpecialFileKind, TransferItem, TransferJob, TransferProgress, TransferStatus};
+use crate::core::drag_drop::{DroppedItem, TransferResult};
+use crate::core::error_recovery::{CopyError, IoOperation, IoResultExt, RecoveryAction};
+use crate::core::locality::{Batch, ScheduleOrder};
+
+/// Construye árboles de `TransferItem` a partir de los elementos soltados
+pub struct TreeScanner {
+    symlink_policy: SymlinkPolicy,
+}
+
+impl TreeScanner {
+    pub fn new(symlink_policy: SymlinkPolicy) -> Self {
+        Self { symlink_policy }
+    }
+
+    /// Escanear cada elemento soltado. Las rutas relativas parten del nombre
+    /// del propio elemento, de modo que `/a/fotos` acaba en `<destino>/fotos`.
+    pub fn scan(&self, items: &[DroppedItem]) -> Result<Vec<TransferItem>, CopyError> {
+        items
+            .iter()
+            .map(|item| {
+                let name = item.path.file_name()
+                    .map(PathBuf::from)
+                    .ok_or(CopyError::InvalidPath)?;
+                self.scan_path(&item.path, name, &mut HashSet::new())
+            })
+            .collect()
+    }
+
+    /// `ancestors` guarda los directorios abiertos en la rama actual para no
+    /// entrar en bucles al seguir enlaces.
+    fn scan_path(
+        &self,
+        path: &Path,
+        relative_path: PathBuf,
+        ancestors: &mut HashSet<(u64, u64)>,
+    ) -> Result<TransferItem, CopyError> {
+        let mut metadata = std::fs::symlink_metadata(path).io_context(IoOperation::Metadata, path)?;
+
+        let mut item = TransferItem {
+            source_path: path.to_path_buf(),
+            relative_path,
+            item_type: ItemType::File,
+            size: 0,
+            children: Vec::new(),
+            status: ItemTransferStatus::Pending,
+            link_target: None,
+            hardlink_key: None,
+        };
+
+        if metadata.file_type().is_symlink() {
+            item.link_target = std::fs::read_link(path).ok();
+
+            let target = match self.symlink_policy {
+                SymlinkPolicy::Dereference => std::fs::metadata(path).ok(),
+                _ => None,
+            };
+
+            match target {
+                // Un enlace que vuelve a un directorio de la rama se deja como enlace
+                Some(target) if !(target.is_dir() && ancestors.contains(&(target.dev(), target.ino()))) => {
+                    metadata = target;
+                }
+                _ => {
+                    item.item_type = ItemType::Symlink;
+                    return Ok(item);
+                }
+            }
+        }
+
+        let file_type = metadata.file_type();
+
+        if file_type.is_dir() {
+            item.item_type = ItemType::Directory;
+            let key = (metadata.dev(), metadata.ino());
+            ancestors.insert(key);
+
+            let mut entries: Vec<_> = std::fs::read_dir(path)
+                .and_then(|entries| entries.collect::<Result<_, _>>())
+                .io_context(IoOperation::ReadDir, path)?;
+
+            // Orden estable para que el recorrido sea reproducible
+            entries.sort_by_key(|entry| entry.file_name());
+
+            for entry in entries {
+                let child = self.scan_path(&entry.path(), item.relative_path.join(entry.file_name()), ancestors)?;
+                item.size += child.size;
+                item.children.push(child);
+            }
+
+            ancestors.remove(&key);
+        } else if file_type.is_file() {
+            item.size = metadata.len();
+            if metadata.nlink() > 1 {
+                item.hardlink_key = Some((metadata.dev(), metadata.ino()));
+            }
+        } else {
+            let kind = if file_type.is_fifo() {
+                SpecialFileKind::Fifo
+            } else if file_type.is_socket() {
+                SpecialFileKind::Socket
+            } else if file_type.is_block_device() {
+                SpecialFileKind::BlockDevice
+            } else {
+                SpecialFileKind::CharDevice
+            };
+            item.item_type = ItemType::Special(kind);
+        }
+
+        Ok(item)
+    }
+
+    /// Número total de elementos y bytes de un conjunto de árboles
+    pub fn totals(items: &[TransferItem]) -> (usize, u64) {
+        items.iter().fold((0, 0), |(count, size), item| {
+            let (child_count, child_size) = Self::totals(&item.children);
+            match item.item_type {
+                ItemType::Directory => (count + 1 + child_count, size + child_size),
+                _ => (count + 1, size + item.size),
+            }
+        })
+    }
+}
+
+/// Estado compartido durante el recorrido de un job
+struct TreeCopyState {
+    /// Pares (raíz de origen, raíz de destino) de cada elemento soltado
+    roots: Vec<(PathBuf, PathBuf)>,
+    /// Primer destino escrito para cada (dev, inode) con varios enlaces duros
+    hardlinks: HashMap<(u64, u64), PathBuf>,
+    /// Archivos que se copian como administrador al final, en una sola sesión
+    elevated: Vec<ElevatedFile>,
+}
+
+/// Archivo con permisos denegados que espera a la sesión de administrador
+struct ElevatedFile {
+    relative_path: PathBuf,
+    destination: PathBuf, // Donde lo registra el diario
+    target: PathBuf,      // Donde se escribe, tras resolver el conflicto
+}
+
+impl TreeCopyState {
+    fn root_pairs(items: &[TransferItem], destination: &Path) -> Vec<(PathBuf, PathBuf)> {
+        items
+            .iter()
+            .flat_map(|item| {
+                // Los enlaces pueden usar la ruta tal cual o la canónica
+                let dest_root = destination.join(&item.relative_path);
+                let canonical = std::fs::canonicalize(&item.source_path).ok()
+                    .filter(|canonical| *canonical != item.source_path);
+                std::iter::once((item.source_path.clone(), dest_root.clone()))
+                    .chain(canonical.map(|canonical| (canonical, dest_root)))
+            })
+            .collect()
+    }
+
+    /// Si un enlace absoluto apunta dentro de lo copiado, traducirlo al destino
+    fn rewrite_link_target(&self, target: &Path) -> PathBuf {
+        if target.is_absolute() {
+            for (source_root, dest_root) in &self.roots {
+                if let Ok(rest) = target.strip_prefix(source_root) {
+                    return dest_root.join(rest);
+                }
+            }
+        }
+
+        target.to_path_buf()
+    }
+}
+
+/// Recorre los árboles de un `TransferJob` recreando su estructura bajo el destino.
+/// El motor debe llevar ya los limitadores de la transferencia y de su cola.
+pub struct TreeCopyExecutor<'a> {
+    engine: &'a crate::core::local_engine::LocalCopyEngine,
+    device_info: crate::core::device_detector::DeviceInfo,
+    config: crate::core::config::AppConfig,
+    order: ScheduleOrder,
+}
+
+impl<'a> TreeCopyExecutor<'a> {
+    pub fn new(
+        engine: &'a crate::core::local_engine::LocalCopyEngine,
+        device_info: crate::core::device_detector::DeviceInfo,
+        config: crate::core::config::AppConfig,
+    ) -> Self {
+        Self { engine, device_info, config, order: ScheduleOrder::Simple }
+    }
+
+    /// Orden de copia, normalmente `ScheduleOrder::for_device` del disco de origen
+    pub fn with_schedule_order(mut self, order: ScheduleOrder) -> Self {
+        self.order = order;
+        self
+    }
+
+    pub async fn execute(&self, job: &mut TransferJob) -> TransferResult {
+        let started = Instant::now();
+        let mut result = TransferResult::new(job.id);
+
+        let (total_items, total_size) = TreeScanner::totals(&job.root_items);
+        job.total_items = total_items;
+        job.total_size = total_size;
+        job.status = TransferStatus::Copying;
+
+        if let Some(journal) = self.engine.journal() {
+            journal.set_status(TransferStatus::Copying);
+        }
+
+        let mut state = TreeCopyState {
+            roots: TreeCopyState::root_pairs(&job.root_items, &job.destination),
+            hardlinks: HashMap::new(),
+            elevated: Vec::new(),
+        };
+
+        // Sacar los árboles del job para poder mutarlos mientras se usa el job
+        let mut items = std::mem::take(&mut job.root_items);
+        self.arrange(&mut items);
+        let mut cancelled = false;
+        let mut next = 0;
+
+        loop {
+            while next < items.len() && !cancelled {
+                if let Err(CopyError::Cancelled) = self.copy_item(&mut items[next], job, &mut state, &mut result).await {
+                    cancelled = true;
+                }
+                next += 1;
+            }
+
+            // Elementos soltados sobre esta transferencia mientras se copiaba
+            let incoming = job.take_incoming_items();
+            if cancelled || incoming.is_empty() {
+                break;
+            }
+
+            let (count, size) = TreeScanner::totals(&incoming);
+            job.total_items += count;
+            job.total_size += size;
+            state.roots.extend(TreeCopyState::root_pairs(&incoming, &job.destination));
+            if let Some(journal) = self.engine.journal() {
+                journal.add_root_items(&incoming);
+            }
+
+            let _ = job.progress_sender.send(TransferProgress::AddItems(incoming.clone())).await;
+            items.extend(incoming);
+            self.arrange(&mut items[next..]);
+        }
+
+        if !cancelled && !state.elevated.is_empty() {
+            if let Err(CopyError::Cancelled) = self.copy_elevated(&mut items, job, &mut state, &mut result).await {
+                cancelled = true;
+            }
+        }
+
+        job.root_items = items;
+
+        let stats = self.engine.get_stats();
+        result.retries = stats.retries;
+        result.active_bytes = stats.bytes_transferred;
+        result.active_duration = stats.active_time;
+        result.interrupted |= self.engine.control().was_paused();
+        result.duration = started.elapsed();
+        result.status = if cancelled {
+            TransferStatus::Cancelled
+        } else if result.errors.is_empty() {
+            TransferStatus::Completed
+        } else {
+            TransferStatus::Error
+        };
+        job.status = result.status.clone();
+
+        // Con errores se conserva el diario para poder reintentar lo que falló, y
+        // al cancelar también si se conservan los temporales, para poder reanudar
+        if let Some(journal) = self.engine.journal() {
+            match result.status {
+                // Lo que falta lo copia otro job en el destino nuevo
+                _ if job.relocate_to.is_some() => journal.finish(),
+                TransferStatus::Error => journal.set_status(TransferStatus::Error),
+                TransferStatus::Cancelled if self.engine.get_options().keep_partial_on_cancel => {
+                    journal.set_status(TransferStatus::Paused)
+                }
+                _ => journal.finish(),
+            }
+        }
+
+        if cancelled {
+            let _ = job.progress_sender.send(TransferProgress::StatusChanged(TransferStatus::Cancelled)).await;
+        }
+
+        result
+    }
+
+    fn copy_item<'b>(
+        &'b self,
+        item: &'b mut TransferItem,
+        job: &'b mut TransferJob,
+        state: &'b mut TreeCopyState,
+        result: &'b mut TransferResult,
+    ) -> Pin<Box<dyn Future<Output = Result<(), CopyError>> + Send + 'b>> {
+        Box::pin(async move {
+            self.wait_if_paused(job).await?;
+
+            let destination = job.destination.join(&item.relative_path);
+            let journal = self.engine.journal();
+
+            // Ya copiado en una ejecución anterior de la misma transferencia
+            let already_done = !matches!(item.item_type, ItemType::Directory)
+                && journal.map(|journal| journal.is_completed(&destination)).unwrap_or(false);
+            if already_done {
+                if let Some(key) = item.hardlink_key {
+                    state.hardlinks.entry(key).or_insert_with(|| destination.clone());
+                }
+                item.status = ItemTransferStatus::Completed;
+                job.completed_items += 1;
+                job.copied_size += item.size;
+                return Ok(());
+            }
+
+            match item.item_type {
+                ItemType::Directory => {
+                    item.status = ItemTransferStatus::CreatingDir;
+
+                    let mut elevate = false;
+                    while let Err(error) = self.create_dir(&destination, elevate).await {
+                        let action = job.handle_error(&item.source_path, &destination, &error, &self.config).await;
+                        match action {
+                            RecoveryAction::Retry => continue,
+                            RecoveryAction::RequestElevation => {
+                                elevate = true;
+                                continue;
+                            }
+                            _ => {}
+                        }
+
+                        item.status = ItemTransferStatus::Error(error.to_string());
+                        result.errors.push(error.to_string());
+                        return match action {
+                            RecoveryAction::Abort => Err(CopyError::Cancelled),
+                            _ => Ok(()),
+                        };
+                    }
+
+                    result.directories_created += 1;
+                    let _ = job.progress_sender.send(TransferProgress::DirectoryCreated {
+                        path: destination.clone(),
+                        item_count: item.children.len(),
+                    }).await;
+
+                    self.copy_children(&mut item.children, job, state, result).await?;
+
+                    // Después de los hijos, para que sus escrituras no cambien el mtime
+                    if self.engine.get_options().preserve_attributes {
+                        self.engine.apply_attributes(&item.source_path, &destination);
+                    }
+
+                    job.completed_items += 1;
+                    item.status = ItemTransferStatus::Completed;
+                }
+                ItemType::File => {
+                    // Tras elegir "Saltar los que no caben" en un destino lleno
+                    if job.skip_oversized {
+                        let available = crate::core::disk_space::available_space(&job.destination).unwrap_or(u64::MAX);
+                        if item.size > available {
+                            let reason = format!("no cabe en el destino ({} MB libres)", available / (1024 * 1024));
+                            self.skip_item(item, reason, job, result).await;
+                            return Ok(());
+                        }
+                    }
+
+                    item.status = ItemTransferStatus::Copying;
+                    let started = Instant::now();
+
+                    let _ = job.progress_sender.send(TransferProgress::FileStarted {
+                        source: item.source_path.clone(),
+                        destination: destination.clone(),
+                        size: item.size,
+                    }).await;
+
+                    // Un destino a medias de esta misma transferencia no es un conflicto
+                    let resuming = journal.map(|journal| journal.owns(&destination)).unwrap_or(false);
+
+                    // Lo que el motor no pudo resolver reintentando se consulta
+                    let mut abort = false;
+                    let mut elevate = false;
+                    let mut space_decision = None;
+                    // El conflicto se resuelve una sola vez; los reintentos, también como
+                    // administrador, escriben donde se decidió
+                    let mut conflict = destination.exists() && !resuming;
+                    let mut target = destination.clone();
+                    // Un archivo ajeno solo pasa a ser de la transferencia al resolver el conflicto
+                    if let (Some(journal), false) = (journal, conflict) {
+                        journal.start_file(&destination);
+                    }
+                    let copied = loop {
+                        let copied = match self.link_to_previous(item, &destination, state) {
+                            true => Ok(()),
+                            false if conflict => {
+                                // Si falla (p. ej. al apartar el existente) se vuelve a resolver
+                                match self.engine.resolve_conflict(&item.source_path, &destination, job, &self.config).await {
+                                    Ok(Some(resolved)) => {
+                                        if let Some(journal) = journal {
+                                            journal.start_file(&destination);
+                                        }
+                                        conflict = false;
+                                        target = resolved;
+                                        continue;
+                                    }
+                                    Ok(None) => {
+                                        // Se deja el existente: ni se cuenta como copiado ni se enlaza
+                                        self.skip_item(item, "ya existe en el destino".to_string(), job, result).await;
+                                        return Ok(());
+                                    }
+                                    Err(e) => Err(e),
+                                }
+                            }
+                            false => {
+                                self.engine.copy_file_optimized(&item.source_path, &target, &self.device_info).await.map(|_| ())
+                            }
+                        };
+
+                        let error = match copied {
+                            Err(CopyError::Cancelled) | Ok(()) => break copied,
+                            Err(error) => error,
+                        };
+                        // Diálogos, esperas y copias como administrador no reflejan la velocidad
+                        result.interrupted = true;
+
+                        // Disco lleno: la cola espera con el temporal guardado
+                        if let CopyError::DiskFull(_) = error {
+                            match self.wait_for_space(&target, job).await {
+                                SpaceDecision::Resume => {
+                                    log::info!("Hay espacio; reanudando {}", item.source_path.display());
+                                    continue;
+                                }
+                                decision => {
+                                    abort = decision == SpaceDecision::Abort;
+                                    space_decision = Some(decision);
+                                    break Err(error);
+                                }
+                            }
+                        }
+
+                        match job.handle_error(&item.source_path, &destination, &error, &self.config).await {
+                            RecoveryAction::Retry => log::info!("Reintentando {}", item.source_path.display()),
+                            RecoveryAction::RequestElevation => {
+                                log::info!("{} se copiará como administrador al final", item.source_path.display());
+                                elevate = true;
+                                break Err(error);
+                            }
+                            action => {
+                                abort = matches!(action, RecoveryAction::Abort);
+                                break Err(error);
+                            }
+                        }
+                    };
+
+                    match space_decision {
+                        Some(SpaceDecision::SkipLargeFiles) => {
+                            job.skip_oversized = true;
+                            self.engine.discard_partial(&target);
+                            self.skip_item(item, "no cabe en el destino".to_string(), job, result).await;
+                            return Ok(());
+                        }
+                        Some(SpaceDecision::MoveTo(new_destination)) => {
+                            // Este archivo se copia entero en el destino nuevo
+                            self.engine.discard_partial(&target);
+                            item.status = ItemTransferStatus::Pending;
+                            job.relocate_to = Some(new_destination);
+                            return Err(CopyError::Cancelled);
+                        }
+                        _ => {}
+                    }
+
+                    // Se copia con los demás denegados en una sola sesión de administrador
+                    if elevate {
+                        self.engine.discard_partial(&target);
+                        state.elevated.push(ElevatedFile {
+                            relative_path: item.relative_path.clone(),
+                            destination,
+                            target,
+                        });
+                        item.status = ItemTransferStatus::Pending;
+                        return Ok(());
+                    }
+
+                    match copied {
+                        Ok(()) => {
+                            if let Some(key) = item.hardlink_key {
+                                state.hardlinks.entry(key).or_insert_with(|| target.clone());
+                            }
+                            if let Some(journal) = journal {
+                                journal.mark_completed(&destination);
+                            }
+
+                            item.status = ItemTransferStatus::Completed;
+                            job.completed_items += 1;
+                            job.copied_size += item.size;
+                            result.files_copied += 1;
+                            result.total_bytes += item.size;
+
+                            let _ = job.progress_sender.send(TransferProgress::FileCompleted {
+                                source: item.source_path.clone(),
+                                destination: target,
+                                size: item.size,
+                                duration: started.elapsed(),
+                            }).await;
+                        }
+                        Err(CopyError::Cancelled) => {
+                            item.status = ItemTransferStatus::Error(CopyError::Cancelled.to_string());
+                            return Err(CopyError::Cancelled);
+                        }
+                        Err(e) => {
+                            item.status = ItemTransferStatus::Error(e.to_string());
+                            result.errors.push(format!("{}: {}", item.source_path.display(), e));
+                            if abort {
+                                return Err(CopyError::Cancelled);
+                            }
+                        }
+                    }
+                }
+                ItemType::Symlink => {
+                    // Con Dereference solo llegan aquí enlaces rotos o que forman bucles
+                    let reason = match (self.engine.get_options().symlink_policy, &item.link_target) {
+                        (SymlinkPolicy::Skip, _) => Some("enlace simbólico omitido".to_string()),
+                        (SymlinkPolicy::Dereference, _) => Some("enlace roto o circular".to_string()),
+                        (SymlinkPolicy::CopyAsLink, None) => Some("no se pudo leer el enlace".to_string()),
+                        (SymlinkPolicy::CopyAsLink, Some(_)) => None,
+                    };
+
+                    if let Some(reason) = reason {
+                        self.skip_item(item, reason, job, result).await;
+                        return Ok(());
+                    }
+
+                    let target = state.rewrite_link_target(item.link_target.as_deref().unwrap_or(Path::new("")));
+
+                    if let Some(journal) = journal {
+                        // Enlace creado antes del corte: se vuelve a crear
+                        if journal.owns(&destination) {
+                            let _ = std::fs::remove_file(&destination);
+                        }
+                        journal.start_file(&destination);
+                    }
+
+                    match std::os::unix::fs::symlink(&target, &destination) {
+                        Ok(()) => {
+                            if self.engine.get_options().preserve_attributes {
+                                self.engine.apply_attributes(&item.source_path, &destination);
+                            }
+                            if let Some(journal) = journal {
+                                journal.mark_completed(&destination);
+                            }
+                            item.status = ItemTransferStatus::Completed;
+                            job.completed_items += 1;
+                        }
+                        Err(e) => {
+                            item.status = ItemTransferStatus::Error(e.to_string());
+                            result.errors.push(format!("{}: {}", item.source_path.display(), e));
+                        }
+                    }
+                }
+                ItemType::Special(kind) => {
+                    // Leer un FIFO o un dispositivo bloquearía o copiaría datos sin fin
+                    let reason = format!("archivo especial no copiable ({:?})", kind);
+                    self.skip_item(item, reason, job, result).await;
+                }
+            }
+
+            Ok(())
+        })
+    }
+
+    /// Copiar como administrador los archivos apartados por permisos denegados,
+    /// todos con una sola autenticación. Devuelve `Cancelled` si se canceló.
+    async fn copy_elevated(
+        &self,
+        items: &mut [TransferItem],
+        job: &mut TransferJob,
+        state: &mut TreeCopyState,
+        result: &mut TransferResult,
+    ) -> Result<(), CopyError> {
+        let elevated = std::mem::take(&mut state.elevated);
+        let started = Instant::now();
+        let mut files = Vec::with_capacity(elevated.len());
+        for file in &elevated {
+            if let Some(item) = find_item(items, &file.relative_path) {
+                item.status = ItemTransferStatus::Copying;
+                files.push((item.source_path.clone(), file.target.clone()));
+            }
+        }
+        log::info!("Copiando {} archivos como administrador", files.len());
+
+        let copied = self.engine.copy_files_elevated(&files).await;
+        let journal = self.engine.journal();
+        let mut outcome = Ok(());
+
+        for (file, copied) in elevated.into_iter().zip(copied) {
+            let Some(item) = find_item(items, &file.relative_path) else { continue };
+            match copied {
+                Ok(()) => {
+                    if let Some(key) = item.hardlink_key {
+                        state.hardlinks.entry(key).or_insert_with(|| file.target.clone());
+                    }
+                    if let Some(journal) = journal {
+                        journal.mark_completed(&file.destination);
+                    }
+
+                    item.status = ItemTransferStatus::Completed;
+                    job.completed_items += 1;
+                    job.copied_size += item.size;
+                    result.files_copied += 1;
+                    result.total_bytes += item.size;
+
+                    let _ = job.progress_sender.send(TransferProgress::FileCompleted {
+                        source: item.source_path.clone(),
+                        destination: file.target,
+                        size: item.size,
+                        duration: started.elapsed(),
+                    }).await;
+                }
+                Err(CopyError::Cancelled) => {
+                    item.status = ItemTransferStatus::Error(CopyError::Cancelled.to_string());
+                    outcome = Err(CopyError::Cancelled);
+                }
+                Err(e) => {
+                    item.status = ItemTransferStatus::Error(e.to_string());
+                    result.errors.push(format!("{}: {}", item.source_path.display(), e));
+                }
+            }
+        }
+
+        outcome
+    }
+
+    /// En orden de localidad, los archivos primero y por posición física; los
+    /// subdirectorios y enlaces después, en el orden del recorrido
+    fn arrange(&self, items: &mut [TransferItem]) {
+        if self.order != ScheduleOrder::Locality {
+            return;
+        }
+
+        items.sort_by_cached_key(|item| match item.item_type {
+            ItemType::File => (0, crate::core::locality::physical_key(&item.source_path)),
+            _ => (1, 0),
+        });
+    }
+
+    /// Copiar el contenido de un directorio. En orden de localidad los archivos
+    /// pequeños van en lotes que se leen por adelantado: el lote entero antes de
+    /// copiarlo o, con `interleave_io`, el siguiente mientras se escribe el actual.
+    async fn copy_children(
+        &self,
+        children: &mut [TransferItem],
+        job: &mut TransferJob,
+        state: &mut TreeCopyState,
+        result: &mut TransferResult,
+    ) -> Result<(), CopyError> {
+        if self.order == ScheduleOrder::Simple {
+            for child in children.iter_mut() {
+                self.copy_item(child, job, state, result).await?;
+            }
+            return Ok(());
+        }
+
+        self.arrange(children);
+        let files = children.iter().take_while(|child| matches!(child.item_type, ItemType::File)).count();
+        let batches = crate::core::locality::plan_batches((0..files).collect(), |&i| children[i].size);
+        let interleave = self.engine.get_options().interleave_io;
+
+        for (n, batch) in batches.iter().enumerate() {
+            let ahead = if interleave { batches.get(n + 1) } else { Some(batch) };
+            if let Some(Batch::Small(indices)) = ahead {
+                let paths: Vec<PathBuf> = indices.iter().map(|&i| children[i].source_path.clone()).collect();
+                let reading = tokio::task::spawn_blocking(move || {
+                    crate::core::locality::prefetch(paths.iter().map(PathBuf::as_path))
+                });
+                if !interleave {
+                    let _ = reading.await;
+                }
+            }
+
+            for &i in batch.items() {
+                self.copy_item(&mut children[i], job, state, result).await?;
+            }
+        }
+
+        for child in children[files..].iter_mut() {
+            self.copy_item(child, job, state, result).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Esperar entre elementos mientras el job esté en pausa, reflejándolo en su estado.
+    /// Dentro de un archivo la pausa la atiende el copiador, con el archivo abierto.
+    async fn wait_if_paused(&self, job: &mut TransferJob) -> Result<(), CopyError> {
+        let control = self.engine.control();
+
+        if control.is_paused() && !control.is_cancelled() {
+            job.status = TransferStatus::Paused;
+            let _ = job.progress_sender.send(TransferProgress::StatusChanged(TransferStatus::Paused)).await;
+
+            control.checkpoint_async().await?;
+
+            job.status = TransferStatus::Copying;
+            let _ = job.progress_sender.send(TransferProgress::StatusChanged(TransferStatus::Copying)).await;
+        }
+
+        control.checkpoint_async().await
+    }
+
+    /// Avisar de que el destino está lleno y esperar a que haya espacio o a que se
+    /// decida otra cosa. Lo que falta se estima con lo que queda por copiar del job,
+    /// descontando lo que ya ocupa el temporal del archivo actual.
+    async fn wait_for_space(&self, destination: &Path, job: &mut TransferJob) -> SpaceDecision {
+        let written = self.engine.partial_offset(destination);
+        let remaining = job.total_size.saturating_sub(job.copied_size).saturating_sub(written);
+        let available = crate::core::disk_space::available_space(&job.destination).unwrap_or(0);
+        let shortage = SpaceShortage::new(&job.destination, remaining, available);
+
+        log::warn!("{}; la transferencia {} espera", shortage, job.id);
+        job.status = TransferStatus::Paused;
+        let _ = job.progress_sender.send(TransferProgress::StatusChanged(TransferStatus::Paused)).await;
+        let _ = job.progress_sender.send(TransferProgress::WaitingForSpace(shortage.clone())).await;
+
+        let decision = job.wait_for_space(shortage).await;
+
+        job.status = TransferStatus::Copying;
+        let _ = job.progress_sender.send(TransferProgress::StatusChanged(TransferStatus::Copying)).await;
+        decision
+    }
+
+    /// Crear un directorio y los que le falten; con `elevated`, a través del ayudante
+    async fn create_dir(&self, path: &Path, elevated: bool) -> Result<(), CopyError> {
+        if !elevated {
+            return std::fs::create_dir_all(path).io_context(IoOperation::CreateDir, path);
+        }
+
+        let path = path.to_path_buf();
+        tokio::task::spawn_blocking(move || crate::core::elevation::create_dir_all(&path, 0o755))
+            .await
+            .map_err(|e| CopyError::Io(e.to_string()))?
+    }
+
+    /// Crear un enlace duro al primer destino copiado del mismo inode
+    fn link_to_previous(&self, item: &TransferItem, destination: &Path, state: &TreeCopyState) -> bool {
+        if !self.engine.get_options().preserve_hardlinks {
+            return false;
+        }
+
+        let Some(first) = item.hardlink_key.and_then(|key| state.hardlinks.get(&key)) else {
+            return false;
+        };
+
+        if destination.exists() {
+            return false;
+        }
+
+        // Si el destino no admite enlaces duros (vfat, exfat...) se copia normalmente
+        std::fs::hard_link(first, destination).is_ok()
+    }
+
+    async fn skip_item(&self, item: &mut TransferItem, reason: String, job: &mut TransferJob, result: &mut TransferResult) {
+        log::warn!("Omitiendo {}: {}", item.source_path.display(), reason);
+
+        item.status = ItemTransferStatus::Skipped(reason.clone());
+        job.completed_items += 1;
+        result.skipped.push(format!("{}: {}", item.source_path.display(), reason));
+
+        let _ = job.progress_sender.send(TransferProgress::ItemSkipped {
+            source: item.source_path.clone(),
+            reason,
+        }).await;
+    }
+}
+
+/// Buscar en los árboles el elemento con esta ruta relativa
+fn find_item<'b>(items: &'b mut [TransferItem], relative_path: &Path) -> Option<&'b mut TransferItem> {
+    for item in items {
+        if item.relative_path == relative_path {
+            return Some(item);
+        }
+        if relative_path.starts_with(&item.relative_path) {
+            return find_item(&mut item.children, relative_path);
+        }
+    }
+    None
+}
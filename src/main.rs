@@ -19,6 +19,22 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let config_manager = Arc::new(Mutex::new(core::config::ConfigManager::new()?));
     let config = config_manager.lock().await.get_config().clone();
     
+    // Límite global de ancho de banda y sus franjas horarias
+    core::throttle::spawn_schedule_task(config_manager.clone());
+    
+    // Planificador de las colas de cada dispositivo
+    let scheduler = Arc::new(core::scheduler::QueueScheduler::new(config_manager.clone()));
+    scheduler.spawn();
+    
+    // Pausar las colas cuyo destino se desconecta y reanudarlas cuando vuelve
+    scheduler.watch_devices(core::device_detector::DeviceMonitor::new().with_uevents(config.hotplug_uevents));
+    
+    // Transferencias que quedaron a medias en la ejecución anterior
+    let transfer_manager = Arc::new(Mutex::new(
+        core::transfer_manager::TransferManager::new().with_scheduler(scheduler.clone())
+    ));
+    let interrupted = transfer_manager.lock().await.load_interrupted().to_vec();
+    
     // Inicializar GTK
     gtk4::init()?;
     
@@ -57,6 +73,23 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             }
         });
         
+        // Diálogo para los errores que no se resuelven reintentando. En modo
+        // daemon no se pregunta: se aplica `unattended_error_action`
+        if !cli.daemon {
+            let (error_service, error_sender) = ui::error_dialog::ErrorDialogService::new(
+                Some(main_window.window.clone())
+            );
+            glib::MainContext::default().spawn_local(error_service.run());
+            scheduler.set_error_dialog(error_sender);
+            
+            // Destino lleno: además de esperar, saltar lo que no cabe o cambiar de destino
+            let (space_service, space_sender) = ui::space_dialog::SpaceDialogService::new(
+                Some(main_window.window.clone())
+            );
+            glib::MainContext::default().spawn_local(space_service.run());
+            scheduler.set_space_dialog(space_sender);
+        }
+        
         // Configurar auto-arranque si está habilitado
         if config.autostart_enabled && !cli.minimized {
             if config.start_minimized {
@@ -68,12 +101,20 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         if cli.daemon {
             main_window.hide_to_tray();
             
-            let mut daemon = core::daemon::CopyMasterDaemon::new();
+            let mut daemon = core::daemon::CopyMasterDaemon::new()
+                .with_transfer_manager(transfer_manager.clone());
             tokio::spawn(async move {
                 let _ = daemon.run_as_daemon().await;
             });
         }
         
+        // Ofrecer reanudar lo que quedó a medias
+        ui::resume_dialog::offer_resume(
+            Some(main_window.window.upcast_ref()),
+            transfer_manager.clone(),
+            &interrupted,
+        );
+        
         setup_signal_handlers(app, main_window);
     });
     
@@ -5,23 +5,7 @@ use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
-#[derive(Clone, Debug)]
-pub struct DroppedItem {
-    pub path: PathBuf,
-    pub size: u64,
-    pub is_dir: bool,
-    pub item_count: Option<usize>,
-}
-
-#[derive(Clone)]
-pub struct TransferRequest {
-    pub id: u64,
-    pub source_items: Vec<DroppedItem>,
-    pub destination: PathBuf,
-    pub device_id: String,
-    pub created_at: std::time::Instant,
-    pub options: crate::core::drag_drop::TransferOptions,
-}
+use crate::core::drag_drop::{DroppedItem, TransferRequest};
 
 pub struct DragDropManager {
     window: gtk::Window,
@@ -40,6 +24,15 @@ impl DragDropManager {
         }
     }
     
+    /// Usar las colas del planificador, para que lo soltado llegue a ejecutarse
+    pub fn with_device_queues(
+        mut self,
+        device_queues: Arc<Mutex<HashMap<String, crate::core::device_queue::DeviceQueue>>>,
+    ) -> Self {
+        self.device_queues = device_queues;
+        self
+    }
+    
     /// Configurar área de drop para un dispositivo específico
     pub fn setup_device_drop_area(&mut self, device_path: &PathBuf, device_name: &str) {
         let drop_target = gtk::DropTarget::new(
@@ -49,56 +42,24 @@ impl DragDropManager {
         
         let device_path = device_path.clone();
         let device_name = device_name.to_string();
-        let pending_transfers = self.pending_transfers.clone();
+        // Las colas van por dispositivo físico, no por nombre
+        let queue_key = crate::core::scheduler::device_key(&device_path);
         let device_queues = self.device_queues.clone();
+        let window = self.window.clone();
         
         drop_target.connect_drop(move |_, value, _, _| {
-            if let Some(file_list) = value.get::<gio::ListModel>() {
+            if let Ok(file_list) = value.get::<gio::ListModel>() {
                 let items = Self::extract_dropped_items(&file_list);
                 
-                // Mostrar diálogo de confirmación
-                let response = Self::show_drop_dialog(&device_name, &items);
-                
-                match response {
-                    DropResponse::NewTransfer => {
-                        let transfer = TransferRequest {
-                            id: rand::random::<u64>(),
-                            source_items: items,
-                            destination: device_path.clone(),
-                            device_id: device_name.clone(),
-                            created_at: std::time::Instant::now(),
-                            options: crate::core::drag_drop::TransferOptions::default(),
-                        };
-                        
-                        // Añadir a cola del dispositivo
-                        tokio::spawn({
-                            let device_queues = device_queues.clone();
-                            let transfer = transfer.clone();
-                            async move {
-                                let mut queues = device_queues.lock().await;
-                                let queue = queues.entry(device_name.clone())
-                                    .or_insert_with(|| crate::core::device_queue::DeviceQueue::new(device_path.clone()));
-                                    
-                                queue.add_transfer(transfer).await;
-                            }
-                        });
-                    }
-                    DropResponse::AddToExisting => {
-                        // Buscar transferencia existente para este dispositivo
-                        tokio::spawn({
-                            let device_queues = device_queues.clone();
-                            let device_name = device_name.clone();
-                            let items = items.clone();
-                            async move {
-                                let mut queues = device_queues.lock().await;
-                                if let Some(queue) = queues.get_mut(&device_name) {
-                                    queue.add_items_to_current_transfer(items).await;
-                                }
-                            }
-                        });
-                    }
-                    DropResponse::Cancel => {}
-                }
+                // Recorrer y comprobar fuera del callback; el diálogo sale al terminar
+                glib::MainContext::default().spawn_local(Self::handle_drop(
+                    window.clone(),
+                    device_path.clone(),
+                    device_name.clone(),
+                    queue_key.clone(),
+                    device_queues.clone(),
+                    items,
+                ));
                 
                 true
             } else {
@@ -110,6 +71,66 @@ impl DragDropManager {
         self.drop_areas.insert(device_name.to_string(), drop_target);
     }
     
+    /// Preparar la transferencia, comprobar el destino y preguntar qué hacer.
+    /// Si la comprobación falla el diálogo lo muestra y solo deja cancelar.
+    async fn handle_drop(
+        window: gtk::Window,
+        device_path: PathBuf,
+        device_name: String,
+        queue_key: String,
+        device_queues: Arc<Mutex<HashMap<String, crate::core::device_queue::DeviceQueue>>>,
+        items: Vec<DroppedItem>,
+    ) {
+        let request = TransferRequest {
+            id: rand::random::<u64>(),
+            source_items: items.clone(),
+            destination: device_path.clone(),
+            device_id: device_name.clone(),
+            created_at: std::time::Instant::now(),
+            options: crate::core::drag_drop::TransferOptions::default(),
+        };
+        
+        let job = match crate::core::device_queue::TransferJob::from_request(request).await {
+            Ok(job) => job,
+            Err(e) => {
+                log::error!("No se pudo preparar la transferencia: {}", e);
+                return;
+            }
+        };
+        
+        let (reserved, existing_transfer) = {
+            let queues = device_queues.lock().await;
+            match queues.get(&queue_key) {
+                Some(queue) => (queue.queued_bytes(), queue.current_transfer.is_some()),
+                None => (0, false),
+            }
+        };
+        let report = crate::core::preflight::check_job(&job, reserved).await;
+        
+        let dialog = crate::ui::drop_dialog::DropDialog::new(Some(&window), &device_name, &items, existing_transfer)
+            .with_preflight(&report);
+        let Some(response) = dialog.run().await else {
+            return;
+        };
+        if !report.is_ok() {
+            return;
+        }
+        
+        let mut queues = device_queues.lock().await;
+        let queue = queues.entry(queue_key)
+            .or_insert_with(|| crate::core::device_queue::DeviceQueue::new(device_path));
+        
+        match response.action {
+            crate::ui::drop_dialog::DropAction::NewTransfer => queue.enqueue(job),
+            crate::ui::drop_dialog::DropAction::AddToExisting => {
+                if let Err(e) = queue.add_items_to_current_transfer(items).await {
+                    log::error!("No se pudieron añadir los elementos: {}", e);
+                }
+            }
+            _ => {}
+        }
+    }
+    
     fn extract_dropped_items(file_list: &gio::ListModel) -> Vec<DroppedItem> {
         let mut items = Vec::new();
         
@@ -144,26 +165,4 @@ impl DragDropManager {
         }
         Ok(count)
     }
-    
-    fn show_drop_dialog(device_name: &str, items: &[DroppedItem]) -> DropResponse {
-        // En una implementación real, mostrarías un diálogo GTK
-        // Por ahora, simulamos la lógica
-        
-        let total_size: u64 = items.iter().map(|i| i.size).sum();
-        let dir_count = items.iter().filter(|i| i.is_dir).count();
-        let file_count = items.len() - dir_count;
-        
-        println!("Soltando en {}: {} archivos, {} carpetas, {} MB",
-                 device_name, file_count, dir_count, total_size / 1024 / 1024);
-        
-        // Verificar si hay transferencia activa para este dispositivo
-        // Si la hay, preguntar si añadir o nueva transferencia
-        DropResponse::NewTransfer // Por defecto
-    }
-}
-
-enum DropResponse {
-    NewTransfer,
-    AddToExisting,
-    Cancel,
 }
\ No newline at end of file
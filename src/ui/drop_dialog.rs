@@ -5,6 +5,8 @@ use std::path::PathBuf;
 pub struct DropDialog {
     dialog: Dialog,
     response: DropDialogResponse,
+    content_area: Box,
+    accept_buttons: Vec<Button>, // Los que inician una copia; se desactivan si la comprobación falla
 }
 
 #[derive(Debug, Clone)]
@@ -26,7 +28,7 @@ impl DropDialog {
     pub fn new(
         parent: Option<&gtk::Window>,
         device_name: &str,
-        items: &[crate::ui::drag_drop::DroppedItem],
+        items: &[crate::core::drag_drop::DroppedItem],
         existing_transfer: bool,
     ) -> Self {
         let dialog = Dialog::new();
@@ -112,6 +114,7 @@ impl DropDialog {
             }
         });
         
+        let add_to_existing_btn_handle = add_to_existing_btn.clone();
         if let Some(btn) = add_to_existing_btn {
             btn.connect_clicked({
                 let dialog = dialog.clone();
@@ -128,7 +131,42 @@ impl DropDialog {
             }
         });
         
-        Self { dialog, response: dialog_response }
+        let mut accept_buttons = vec![new_transfer_btn];
+        accept_buttons.extend(add_to_existing_btn_handle);
+        
+        Self { dialog, response: dialog_response, content_area, accept_buttons }
+    }
+    
+    /// Mostrar lo que impide la copia antes de escribir nada. Con problemas
+    /// solo se puede cancelar.
+    pub fn with_preflight(self, report: &crate::core::preflight::PreflightReport) -> Self {
+        if report.is_ok() {
+            return self;
+        }
+        
+        let problems_box = Box::new(gtk::Orientation::Vertical, 5);
+        problems_box.add_css_class("error");
+        
+        let title_box = Box::new(gtk::Orientation::Horizontal, 10);
+        title_box.append(&Image::from_icon_name("dialog-warning"));
+        title_box.append(&Label::new(Some("No se puede copiar a este destino:")));
+        problems_box.append(&title_box);
+        
+        for problem in &report.problems {
+            let label = Label::new(Some(&format!("• {}", problem)));
+            label.set_xalign(0.0);
+            label.set_wrap(true);
+            problems_box.append(&label);
+        }
+        
+        // Antes de las opciones, para que se lea primero
+        self.content_area.insert_child_after(&problems_box, self.content_area.first_child().as_ref());
+        
+        for button in &self.accept_buttons {
+            button.set_sensitive(false);
+        }
+        
+        self
     }
     
     pub async fn run(&self) -> Option<DropDialogResponse> {
@@ -153,7 +191,7 @@ impl DropDialog {
         }
     }
     
-    fn calculate_stats(items: &[crate::ui::drag_drop::DroppedItem]) -> DropStats {
+    fn calculate_stats(items: &[crate::core::drag_drop::DroppedItem]) -> DropStats {
         let mut stats = DropStats {
             file_count: 0,
             dir_count: 0,
@@ -0,0 +1,210 @@
+// src/ui/error_dialog.rs
+// Preguntar qué hacer con un error que los reintentos automáticos no resolvieron
+use gtk4::{prelude::*, Dialog, Label, Button, Box, Image, ResponseType, Frame};
+use std::path::PathBuf;
+use tokio::sync::oneshot;
+
+use crate::core::error_recovery::{CopyError, RecoveryAction};
+
+#[derive(Debug)]
+pub struct ErrorDialogRequest {
+    pub source: PathBuf,
+    pub destination: PathBuf,
+    pub error: CopyError,
+    pub transfer_name: String,
+    pub response_sender: oneshot::Sender<RecoveryAction>,
+}
+
+pub struct ErrorDialog {
+    dialog: Dialog,
+}
+
+impl ErrorDialog {
+    pub fn new(
+        parent: Option<&gtk::Window>,
+        request: &ErrorDialogRequest,
+    ) -> Self {
+        let dialog = Dialog::new();
+
+        if let Some(p) = parent {
+            dialog.set_transient_for(Some(p));
+        }
+
+        dialog.set_title(Some(&format!("Error en '{}'", request.transfer_name)));
+        dialog.set_default_size(560, 320);
+        dialog.set_modal(true);
+
+        let content_area = dialog.content_area();
+        content_area.set_spacing(15);
+        content_area.set_margin_all(20);
+
+        // Encabezado
+        let header_box = Box::new(gtk::Orientation::Horizontal, 10);
+        let error_icon = Image::from_icon_name("dialog-error");
+        error_icon.set_pixel_size(48);
+
+        let title_label = Label::new(Some("No se pudo copiar el archivo"));
+        title_label.add_css_class("title-4");
+
+        header_box.append(&error_icon);
+        header_box.append(&title_label);
+
+        let message_label = Label::new(Some(&format!(
+            "{}\n\n{}",
+            request.source.display(),
+            request.error
+        )));
+        message_label.set_wrap(true);
+        message_label.set_selectable(true);
+        message_label.set_xalign(0.0);
+
+        content_area.append(&header_box);
+        content_area.append(&message_label);
+
+        if let Some(hint) = Self::hint(&request.error) {
+            let frame = Frame::new(None);
+            let hint_label = Label::new(Some(hint));
+            hint_label.set_wrap(true);
+            hint_label.set_xalign(0.0);
+            hint_label.set_margin_all(10);
+            frame.set_child(Some(&hint_label));
+            content_area.append(&frame);
+        }
+
+        let can_elevate = matches!(request.error.suggested_action(), RecoveryAction::RequestElevation);
+        content_area.append(&Self::create_actions_box(&dialog, can_elevate));
+
+        Self { dialog }
+    }
+
+    /// Qué puede hacer el usuario antes de pulsar "Reintentar"
+    fn hint(error: &CopyError) -> Option<&'static str> {
+        match error.suggested_action() {
+            RecoveryAction::FreeSpace => Some("Libera espacio en el destino y pulsa Reintentar."),
+            RecoveryAction::RequestElevation => Some(
+                "Comprueba los permisos del origen y del destino, o pulsa \"Reintentar como administrador\" \
+                 para escribir en el destino con la contraseña de administrador.",
+            ),
+            RecoveryAction::UnlockOrSkip => Some("Cierra el programa que está usando el archivo y pulsa Reintentar."),
+            _ => None,
+        }
+    }
+
+    fn create_actions_box(dialog: &Dialog, can_elevate: bool) -> gtk::Box {
+        let actions_box = Box::new(gtk::Orientation::Horizontal, 8);
+        actions_box.set_halign(gtk::Align::End);
+
+        // Botón: Reintentar
+        let retry_btn = Button::with_label("Reintentar");
+        retry_btn.add_css_class("suggested-action");
+        retry_btn.connect_clicked({
+            let dialog = dialog.clone();
+            move |_| {
+                dialog.emit_response(ResponseType::Other(0)); // Retry
+            }
+        });
+
+        // Botón: Saltar
+        let skip_btn = Button::with_label("Saltar");
+        skip_btn.set_tooltip_text(Some("Dejar este archivo sin copiar y seguir"));
+        skip_btn.connect_clicked({
+            let dialog = dialog.clone();
+            move |_| {
+                dialog.emit_response(ResponseType::Other(1)); // Skip
+            }
+        });
+
+        // Botón: Saltar todos (esta copia)
+        let skip_all_btn = Button::with_label("Saltar todos");
+        skip_all_btn.set_tooltip_text(Some("No volver a preguntar en esta copia: saltar cada archivo que falle"));
+        skip_all_btn.connect_clicked({
+            let dialog = dialog.clone();
+            move |_| {
+                dialog.emit_response(ResponseType::Other(2)); // SkipAll
+            }
+        });
+
+        // Botón: Abortar
+        let abort_btn = Button::with_label("Abortar copia");
+        abort_btn.add_css_class("destructive-action");
+        abort_btn.connect_clicked({
+            let dialog = dialog.clone();
+            move |_| {
+                dialog.emit_response(ResponseType::Cancel);
+            }
+        });
+
+        actions_box.append(&abort_btn);
+        actions_box.append(&skip_all_btn);
+        actions_box.append(&skip_btn);
+
+        // Botón: Reintentar como administrador (solo para errores de permisos)
+        if can_elevate {
+            let elevate_btn = Button::with_label("Reintentar como administrador");
+            elevate_btn.set_tooltip_text(Some("Pedir la contraseña de administrador y repetir solo esta operación"));
+            elevate_btn.connect_clicked({
+                let dialog = dialog.clone();
+                move |_| {
+                    dialog.emit_response(ResponseType::Other(3)); // RequestElevation
+                }
+            });
+            actions_box.append(&elevate_btn);
+        }
+
+        actions_box.append(&retry_btn);
+
+        actions_box
+    }
+
+    pub async fn run(&self) -> RecoveryAction {
+        let response = self.dialog.run_future().await;
+        self.dialog.close();
+
+        match response {
+            ResponseType::Other(0) => RecoveryAction::Retry,
+            ResponseType::Other(1) => RecoveryAction::Skip,
+            ResponseType::Other(2) => RecoveryAction::SkipAll,
+            ResponseType::Other(3) => RecoveryAction::RequestElevation,
+            // Cerrar la ventana cuenta como abortar: es lo que no pierde nada
+            _ => RecoveryAction::Abort,
+        }
+    }
+}
+
+// Servicio para manejar diálogos de error
+pub struct ErrorDialogService {
+    request_receiver: tokio::sync::mpsc::Receiver<ErrorDialogRequest>,
+    window: Option<gtk::Window>,
+}
+
+impl ErrorDialogService {
+    pub fn new(window: Option<gtk::Window>) -> (Self, tokio::sync::mpsc::Sender<ErrorDialogRequest>) {
+        let (sender, receiver) = tokio::sync::mpsc::channel(100);
+
+        let service = Self {
+            request_receiver: receiver,
+            window,
+        };
+
+        (service, sender)
+    }
+
+    /// Atender las peticiones de una en una; debe correr en el hilo de GTK
+    pub async fn run(mut self) {
+        while let Some(request) = self.request_receiver.recv().await {
+            self.handle_request(request).await;
+        }
+    }
+
+    async fn handle_request(&self, request: ErrorDialogRequest) {
+        // La copia puede haberse cancelado mientras la petición esperaba
+        if request.response_sender.is_closed() {
+            return;
+        }
+
+        let dialog = ErrorDialog::new(self.window.as_ref(), &request);
+        let action = dialog.run().await;
+
+        let _ = request.response_sender.send(action);
+    }
+}
@@ -189,6 +189,28 @@ impl MainWindow {
         });
         
         app.add_action(&about_action);
+        
+        // Acciones del menú de la bandeja sobre todas las transferencias
+        let pause_all_action = gio::SimpleAction::new("pause_all", None);
+        pause_all_action.connect_activate(|_, _| {
+            crate::core::control::global_control().pause();
+        });
+        
+        app.add_action(&pause_all_action);
+        
+        let resume_all_action = gio::SimpleAction::new("resume_all", None);
+        resume_all_action.connect_activate(|_, _| {
+            crate::core::control::global_control().resume();
+        });
+        
+        app.add_action(&resume_all_action);
+        
+        let cancel_all_action = gio::SimpleAction::new("cancel_all", None);
+        cancel_all_action.connect_activate(|_, _| {
+            crate::core::control::global_control().cancel();
+        });
+        
+        app.add_action(&cancel_all_action);
     }
     
     pub fn set_conflict_service(&mut self, service: Arc<tokio::sync::Mutex<crate::ui::conflict_dialog::ConflictDialogService>>) {
@@ -2,8 +2,11 @@ pub mod conflict_dialog;
 pub mod settings_dialog;
 pub mod drag_drop;
 pub mod drop_dialog;
+pub mod error_dialog;
 pub mod main_window;
 pub mod queue_panel;
+pub mod resume_dialog;
+pub mod space_dialog;
 pub mod style;
 pub mod tray_icon;
 pub mod devices_panel;
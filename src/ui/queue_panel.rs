@@ -71,7 +71,7 @@ impl QueuePanel {
                     async move {
                         let mut queues_lock = queues.lock().await;
                         if let Some(queue) = queues_lock.get_mut(&device_name) {
-                            // queue.pause().await;
+                            queue.pause();
                         }
                     }
                 });
@@ -89,7 +89,7 @@ impl QueuePanel {
                     async move {
                         let mut queues_lock = queues.lock().await;
                         if let Some(queue) = queues_lock.get_mut(&device_name) {
-                            // queue.resume().await;
+                            queue.resume();
                         }
                     }
                 });
@@ -98,6 +98,22 @@ impl QueuePanel {
         
         let cancel_btn = Button::with_label("Cancelar");
         cancel_btn.add_css_class("destructive-action");
+        cancel_btn.connect_clicked({
+            let device_name = device_name.to_string();
+            let queues = self.device_queues.clone();
+            move |_| {
+                tokio::spawn({
+                    let device_name = device_name.clone();
+                    let queues = queues.clone();
+                    async move {
+                        let mut queues_lock = queues.lock().await;
+                        if let Some(queue) = queues_lock.get_mut(&device_name) {
+                            queue.cancel();
+                        }
+                    }
+                });
+            }
+        });
         
         controls_box.append(&pause_btn);
         controls_box.append(&resume_btn);
@@ -162,8 +178,27 @@ impl QueuePanel {
             transfer.total_items
         )));
         
+        // Prioridad, modificable mientras la transferencia espera
+        let priority_dropdown = gtk::DropDown::from_strings(&["Segundo plano", "Normal", "Interactiva", "Crítica"]);
+        priority_dropdown.set_selected(transfer.priority.clone() as u32);
+        priority_dropdown.connect_selected_notify({
+            let job_id = transfer.id;
+            let queues = self.device_queues.clone();
+            move |dropdown| {
+                let Some(priority) = crate::core::device_queue::QueuePriority::ALL.get(dropdown.selected() as usize).cloned() else {
+                    return;
+                };
+                let queues = queues.clone();
+                tokio::spawn(async move {
+                    let mut queues_lock = queues.lock().await;
+                    queues_lock.values_mut().any(|queue| queue.set_priority(job_id, priority.clone()));
+                });
+            }
+        });
+        
         item_box.append(&icon);
         item_box.append(&info_label);
+        item_box.append(&priority_dropdown);
         
         item_box
     }
@@ -0,0 +1,77 @@
+// src/ui/resume_dialog.rs
+use gtk4::{prelude::*, MessageDialog};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// Ofrecer al arrancar la reanudación de las transferencias que quedaron a medias
+pub fn offer_resume(
+    parent: Option<&gtk::Window>,
+    transfer_manager: Arc<Mutex<crate::core::transfer_manager::TransferManager>>,
+    records: &[crate::core::journal::JournalRecord],
+) {
+    if records.is_empty() {
+        return;
+    }
+    
+    let details = records
+        .iter()
+        .map(|record| format!(
+            "• {} → {} ({} de {} MB)",
+            record.transfer_name,
+            record.destination.display(),
+            record.copied_size() / 1024 / 1024,
+            record.total_size / 1024 / 1024,
+        ))
+        .collect::<Vec<_>>()
+        .join("\n");
+    
+    let dialog = MessageDialog::builder()
+        .modal(true)
+        .message_type(gtk::MessageType::Question)
+        .text(&format!("Hay {} transferencias interrumpidas", records.len()))
+        .secondary_text(&details)
+        .build();
+    
+    if let Some(p) = parent {
+        dialog.set_transient_for(Some(p));
+    }
+    
+    dialog.add_button("Más tarde", gtk::ResponseType::Cancel);
+    dialog.add_button("Descartar", gtk::ResponseType::Reject);
+    dialog.add_button("Reanudar", gtk::ResponseType::Accept);
+    dialog.set_default_response(gtk::ResponseType::Accept);
+    
+    let job_ids: Vec<u64> = records.iter().map(|record| record.job_id).collect();
+    
+    dialog.connect_response(move |dialog, response| {
+        let transfer_manager = transfer_manager.clone();
+        let job_ids = job_ids.clone();
+        
+        match response {
+            gtk::ResponseType::Accept => {
+                tokio::spawn(async move {
+                    let mut manager = transfer_manager.lock().await;
+                    for job_id in job_ids {
+                        manager.resume_interrupted(job_id).await;
+                    }
+                });
+            }
+            gtk::ResponseType::Reject => {
+                tokio::spawn(async move {
+                    let mut manager = transfer_manager.lock().await;
+                    for job_id in job_ids {
+                        if let Err(e) = manager.discard_interrupted(job_id) {
+                            log::warn!("No se pudo descartar la transferencia {}: {}", job_id, e);
+                        }
+                    }
+                });
+            }
+            // Se volverán a ofrecer en el próximo arranque
+            _ => {}
+        }
+        
+        dialog.close();
+    });
+    
+    dialog.show();
+}
@@ -0,0 +1,194 @@
+// src/ui/space_dialog.rs
+// Destino lleno: la copia espera sola a que haya espacio; este diálogo ofrece
+// no esperar, saltando lo que no cabe o llevando lo que falta a otro destino
+use gtk4::{prelude::*, Dialog, Label, Button, Box, Image, ResponseType, FileChooserAction, FileChooserNative};
+use std::path::PathBuf;
+
+use crate::core::disk_space::{SpaceDecision, SpaceRequest};
+
+pub struct SpaceDialog {
+    dialog: Dialog,
+    parent: Option<gtk::Window>,
+}
+
+impl SpaceDialog {
+    pub fn new(
+        parent: Option<&gtk::Window>,
+        request: &SpaceRequest,
+    ) -> Self {
+        let dialog = Dialog::new();
+
+        if let Some(p) = parent {
+            dialog.set_transient_for(Some(p));
+        }
+
+        dialog.set_title(Some(&format!("Destino lleno en '{}'", request.transfer_name)));
+        dialog.set_default_size(520, 260);
+        dialog.set_modal(false);
+
+        let content_area = dialog.content_area();
+        content_area.set_spacing(15);
+        content_area.set_margin_all(20);
+
+        // Encabezado
+        let header_box = Box::new(gtk::Orientation::Horizontal, 10);
+        let icon = Image::from_icon_name("drive-harddisk");
+        icon.set_pixel_size(48);
+
+        let title_label = Label::new(Some("No queda espacio en el destino"));
+        title_label.add_css_class("title-4");
+
+        header_box.append(&icon);
+        header_box.append(&title_label);
+
+        let message_label = Label::new(Some(&format!(
+            "{}\n\nLa cola está en pausa y el archivo a medias se conserva. \
+             La copia seguirá sola en cuanto haya espacio.",
+            request.shortage
+        )));
+        message_label.set_wrap(true);
+        message_label.set_xalign(0.0);
+
+        content_area.append(&header_box);
+        content_area.append(&message_label);
+        content_area.append(&Self::create_actions_box(&dialog));
+
+        Self {
+            dialog,
+            parent: parent.cloned(),
+        }
+    }
+
+    fn create_actions_box(dialog: &Dialog) -> gtk::Box {
+        let actions_box = Box::new(gtk::Orientation::Horizontal, 8);
+        actions_box.set_halign(gtk::Align::End);
+
+        // Botón: Esperar (solo cierra el diálogo)
+        let wait_btn = Button::with_label("Esperar");
+        wait_btn.add_css_class("suggested-action");
+        wait_btn.set_tooltip_text(Some("Seguir cuando se libere espacio"));
+        wait_btn.connect_clicked({
+            let dialog = dialog.clone();
+            move |_| {
+                dialog.emit_response(ResponseType::Other(0)); // Esperar
+            }
+        });
+
+        // Botón: Saltar los que no caben
+        let skip_btn = Button::with_label("Saltar los que no caben");
+        skip_btn.set_tooltip_text(Some("Saltar este archivo y los que no quepan en el espacio libre"));
+        skip_btn.connect_clicked({
+            let dialog = dialog.clone();
+            move |_| {
+                dialog.emit_response(ResponseType::Other(1)); // SkipLargeFiles
+            }
+        });
+
+        // Botón: Mover a otro destino
+        let move_btn = Button::with_label("Mover a otro destino…");
+        move_btn.set_tooltip_text(Some("Copiar lo que falta en otra carpeta; lo ya copiado se queda aquí"));
+        move_btn.connect_clicked({
+            let dialog = dialog.clone();
+            move |_| {
+                dialog.emit_response(ResponseType::Other(2)); // MoveTo
+            }
+        });
+
+        // Botón: Abortar
+        let abort_btn = Button::with_label("Abortar copia");
+        abort_btn.add_css_class("destructive-action");
+        abort_btn.connect_clicked({
+            let dialog = dialog.clone();
+            move |_| {
+                dialog.emit_response(ResponseType::Cancel);
+            }
+        });
+
+        actions_box.append(&abort_btn);
+        actions_box.append(&move_btn);
+        actions_box.append(&skip_btn);
+        actions_box.append(&wait_btn);
+
+        actions_box
+    }
+
+    /// Lo que elija el usuario; `None` es seguir esperando
+    pub async fn run(&self) -> Option<SpaceDecision> {
+        let response = self.dialog.run_future().await;
+        self.dialog.close();
+
+        match response {
+            ResponseType::Other(1) => Some(SpaceDecision::SkipLargeFiles),
+            ResponseType::Other(2) => self.choose_destination().await.map(SpaceDecision::MoveTo),
+            ResponseType::Cancel => Some(SpaceDecision::Abort),
+            _ => None,
+        }
+    }
+
+    pub fn close(&self) {
+        self.dialog.close();
+    }
+
+    async fn choose_destination(&self) -> Option<PathBuf> {
+        let chooser = FileChooserNative::new(
+            Some("Mover lo que falta a…"),
+            self.parent.as_ref(),
+            FileChooserAction::SelectFolder,
+            Some("Mover aquí"),
+            Some("Cancelar"),
+        );
+
+        let response = chooser.run_future().await;
+        if response != ResponseType::Accept {
+            return None;
+        }
+        chooser.file().and_then(|file| file.path())
+    }
+}
+
+// Servicio para manejar diálogos de destino lleno
+pub struct SpaceDialogService {
+    request_receiver: tokio::sync::mpsc::Receiver<SpaceRequest>,
+    window: Option<gtk::Window>,
+}
+
+impl SpaceDialogService {
+    pub fn new(window: Option<gtk::Window>) -> (Self, tokio::sync::mpsc::Sender<SpaceRequest>) {
+        let (sender, receiver) = tokio::sync::mpsc::channel(100);
+
+        let service = Self {
+            request_receiver: receiver,
+            window,
+        };
+
+        (service, sender)
+    }
+
+    /// Atender las peticiones de una en una; debe correr en el hilo de GTK
+    pub async fn run(mut self) {
+        while let Some(request) = self.request_receiver.recv().await {
+            self.handle_request(request).await;
+        }
+    }
+
+    async fn handle_request(&self, mut request: SpaceRequest) {
+        // Puede que ya haya espacio o que la copia se haya cancelado
+        if request.response_sender.is_closed() {
+            return;
+        }
+
+        let dialog = SpaceDialog::new(self.window.as_ref(), &request);
+        let decision = tokio::select! {
+            decision = dialog.run() => decision,
+            // La copia ya siguió sola: el diálogo sobra
+            _ = request.response_sender.closed() => {
+                dialog.close();
+                return;
+            }
+        };
+
+        if let Some(decision) = decision {
+            let _ = request.response_sender.send(decision);
+        }
+    }
+}
@@ -97,25 +97,30 @@ impl SystemTray {
     }
     
     pub fn show_notification(&self, title: &str, message: &str, icon_name: Option<&str>) {
-        // Usar libnotify para notificaciones del sistema
-        #[cfg(feature = "notifications")]
-        {
-            if let Err(e) = notify_rust::Notification::new()
-                .summary(title)
-                .body(message)
-                .icon(icon_name.unwrap_or("copymaster"))
-                .show() 
-            {
-                eprintln!("Error al mostrar notificación: {}", e);
-            }
-        }
-        
-        // Fallback: solo imprimir en consola
-        #[cfg(not(feature = "notifications"))]
+        notify(title, message, icon_name);
+    }
+}
+
+/// Notificación del sistema, también sin bandeja (p. ej. desde el planificador)
+pub fn notify(title: &str, message: &str, icon_name: Option<&str>) {
+    // Usar libnotify para notificaciones del sistema
+    #[cfg(feature = "notifications")]
+    {
+        if let Err(e) = notify_rust::Notification::new()
+            .summary(title)
+            .body(message)
+            .icon(icon_name.unwrap_or("copymaster"))
+            .show() 
         {
-            println!("{}: {}", title, message);
+            eprintln!("Error al mostrar notificación: {}", e);
         }
     }
+    
+    // Fallback: solo imprimir en consola
+    #[cfg(not(feature = "notifications"))]
+    {
+        println!("{}: {}", title, message);
+    }
 }
 
 // Implementación GTK nativa para StatusIcon